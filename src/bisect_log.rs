@@ -0,0 +1,147 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use crate::{regression::TestResult, terms::Terms};
+
+/// Reads the results of a `git bisect log`. `good`/`old` and `bad`/`new` are
+/// the states of the sources and targets, like the terms of the search.
+/// Everything else, e.g. `git bisect start`, is ignored.
+pub fn read(path: &Path, terms: &Terms) -> Result<Vec<(String, TestResult)>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+    let mut results = vec![];
+
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let command = match line
+            .strip_prefix("git bisect ")
+            .or_else(|| line.strip_prefix("git-bisect "))
+        {
+            Some(command) => command,
+            //Comments and empty lines.
+            None => continue,
+        };
+        let mut words = command.split_whitespace();
+        let term = words.next().unwrap_or_default();
+
+        if ["start", "terms", "reset", "replay", "log", "run"].contains(&term) {
+            continue;
+        }
+        let result = terms
+            .parse(term)
+            .map_err(|err| format!("{}:{}: {}", path.display(), number + 1, err))?;
+
+        //git writes the full hashes, quoted if the log was edited by hand.
+        results.extend(words.map(|commit| (commit.trim_matches('\'').to_string(), result.clone())));
+    }
+    Ok(results)
+}
+
+/// Starts a log that `git bisect replay` understands.
+pub fn create(path: &Path, terms: &Terms) {
+    let mut start = "git bisect start".to_string();
+    if terms.old != "good" || terms.new != "bad" {
+        start.push_str(&format!(
+            " '--term-old' '{}' '--term-new' '{}'",
+            terms.old, terms.new
+        ));
+    }
+    fs::write(path, format!("{}\n", start)).expect("Couldn't create bisect log!");
+}
+
+pub fn append(path: &Path, commit: &str, result: &TestResult, terms: &Terms) {
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(path)
+        .expect("Couldn't open bisect log!");
+    writeln!(&mut file, "git bisect {} {}", terms.name(result), commit)
+        .expect("Couldn't write to bisect log!");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_log(
+        name: &str,
+        content: &str,
+        terms: &Terms,
+    ) -> Result<Vec<(String, TestResult)>, String> {
+        let path = std::env::temp_dir().join(format!("crs_{}_{}", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        let results = read(&path, terms);
+        fs::remove_file(&path).unwrap();
+        results
+    }
+
+    #[test]
+    fn reads_results_of_git() {
+        let log = "git bisect start
+# bad: [c0ffee] broken
+git bisect bad c0ffee
+# good: [facade] works
+git bisect good facade
+git-bisect skip 'decade' 'beaded'
+git bisect new 'feed'
+";
+        assert_eq!(
+            read_log("git", log, &Terms::default()),
+            Ok(vec![
+                ("c0ffee".to_string(), TestResult::False),
+                ("facade".to_string(), TestResult::True),
+                ("decade".to_string(), TestResult::Ignore),
+                ("beaded".to_string(), TestResult::Ignore),
+                ("feed".to_string(), TestResult::False),
+            ])
+        );
+    }
+
+    #[test]
+    fn reads_the_terms_of_the_search() {
+        let terms = Terms::new(true, None, None);
+        let log = "git bisect start '--term-old' 'broken' '--term-new' 'fixed'
+git bisect fixed c0ffee
+git bisect broken facade
+";
+        assert_eq!(
+            read_log("terms", log, &terms),
+            Ok(vec![
+                ("c0ffee".to_string(), TestResult::False),
+                ("facade".to_string(), TestResult::True),
+            ])
+        );
+
+        let error = read_log(
+            "unknown",
+            "git bisect start\ngit bisect good c0ffee\n",
+            &terms,
+        );
+        assert!(error
+            .unwrap_err()
+            .ends_with(":2: unknown result \"good\", expected broken, fixed or skip"));
+    }
+
+    #[test]
+    fn reads_the_export() {
+        let terms = Terms::new(false, Some("fast".to_string()), Some("slow".to_string()));
+        let path = std::env::temp_dir().join(format!("crs_export_{}", std::process::id()));
+        create(&path, &terms);
+        append(&path, "c0ffee", &TestResult::False, &terms);
+        append(&path, "facade", &TestResult::True, &terms);
+        append(&path, "decade", &TestResult::Ignore, &terms);
+        let results = read(&path, &terms);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            results,
+            Ok(vec![
+                ("c0ffee".to_string(), TestResult::False),
+                ("facade".to_string(), TestResult::True),
+                ("decade".to_string(), TestResult::Ignore),
+            ])
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{dvcs::DVCS, regression::TestResult};
+
+pub enum Lookup {
+    /// A commit with the same tree has already been tested. Contains its
+    /// result and the tested commit.
+    Hit(TestResult, String),
+    /// A commit with the same tree is tested right now. The commit gets the
+    /// result of that query.
+    Pending,
+    /// The commit has to be tested. Contains the tree of the commit, if it is
+    /// known.
+    Miss(Option<String>),
+}
+
+/// Caches test results by the tree of a commit, so commits with identical
+/// content (e.g. reverts or merges without changes) are only tested once.
+pub struct TreeCache {
+    trees: HashMap<String, String>,
+    results: HashMap<String, (TestResult, String)>,
+    running: HashMap<String, String>,
+    waiting: HashMap<String, Vec<String>>,
+    retry: VecDeque<String>,
+}
+
+impl TreeCache {
+    pub fn new() -> Self {
+        TreeCache {
+            trees: HashMap::new(),
+            results: HashMap::new(),
+            running: HashMap::new(),
+            waiting: HashMap::new(),
+            retry: VecDeque::new(),
+        }
+    }
+
+    pub fn lookup<T: DVCS>(&mut self, repository: &str, commit: &str) -> Lookup {
+        let tree = match T::tree_hash(repository, commit) {
+            Some(tree) => tree,
+            None => return Lookup::Miss(None),
+        };
+        self.trees.insert(commit.to_string(), tree.clone());
+
+        if let Some((result, commit)) = self.results.get(&tree) {
+            Lookup::Hit(result.clone(), commit.clone())
+        } else if self.running.contains_key(&tree) {
+            self.waiting
+                .entry(tree)
+                .or_default()
+                .push(commit.to_string());
+            Lookup::Pending
+        } else {
+            self.running.insert(tree.clone(), commit.to_string());
+            Lookup::Miss(Some(tree))
+        }
+    }
+
+    /// Stores the result of a query. Returns all commits that are waiting for
+    /// this result.
+    pub fn insert(&mut self, commit: &str, result: TestResult) -> Vec<String> {
+        match self.trees.get(commit) {
+            Some(tree) => {
+                self.running.remove(tree);
+                self.results
+                    .insert(tree.clone(), (result, commit.to_string()));
+                self.waiting.remove(tree).unwrap_or_default()
+            }
+            None => vec![],
+        }
+    }
+
+    /// The query of a commit has no result. The commits that are waiting for
+    /// it have to be tested themselves, see [`TreeCache::next_retry`].
+    pub fn abort(&mut self, commit: &str) {
+        if let Some(tree) = self.trees.get(commit) {
+            if self.running.get(tree).map(|c| c == commit).unwrap_or(false) {
+                self.running.remove(tree);
+                let waiting = self.waiting.remove(tree).unwrap_or_default();
+                self.retry.extend(waiting);
+            }
+        }
+    }
+
+    /// Removes a commit that is waiting for a result, e.g. because the result
+    /// is not relevant anymore.
+    pub fn cancel(&mut self, commit: &str) {
+        if let Some(tree) = self.trees.get(commit) {
+            if let Some(waiting) = self.waiting.get_mut(tree) {
+                waiting.retain(|c| c != commit);
+            }
+        }
+        self.retry.retain(|c| c != commit);
+    }
+
+    /// Commit that has to be queried again, because the query it was waiting
+    /// for had no result.
+    pub fn next_retry(&mut self) -> Option<String> {
+        self.retry.pop_front()
+    }
+}
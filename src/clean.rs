@@ -0,0 +1,39 @@
+use std::path::Path;
+
+use crate::dvcs::DVCS;
+
+/// Removes all worktrees that were created by crs, e.g. by runs that crashed.
+pub fn clean<T: DVCS>(repository: &str) {
+    let worktrees = match T::list_worktrees(repository) {
+        Ok(worktrees) => worktrees,
+        Err(_) => {
+            eprintln!("Couldn't list worktrees of {}", repository);
+            return;
+        }
+    };
+
+    if worktrees.is_empty() {
+        println!("No worktrees of crs found.");
+        return;
+    }
+
+    let mut removed = 0;
+    for worktree in &worktrees {
+        //Worktrees without a directory are removed by pruning.
+        if !Path::new(&worktree.location).exists() {
+            println!("Prune {} (directory is missing)", worktree.location);
+            removed += 1;
+        } else if T::remove_worktree(worktree).is_ok() {
+            println!("Removed {}", worktree.location);
+            removed += 1;
+        } else {
+            eprintln!("Couldn't remove {}", worktree.location);
+        }
+    }
+
+    if T::prune_worktrees(repository).is_err() {
+        eprintln!("Couldn't prune worktrees of {}", repository);
+    }
+
+    println!("Removed {} of {} worktrees.", removed, worktrees.len());
+}
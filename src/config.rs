@@ -0,0 +1,345 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{ArgMatches, ValueSource};
+use serde::Deserialize;
+
+use crate::{regression::interval_search::Scheduling, Args};
+
+pub const FILE_NAME: &str = "crs.toml";
+
+/// Options that can be set in the `[defaults]` table or in a profile of a
+/// configuration file. Relative paths are relative to the configuration file.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Settings {
+    /// Name of a test in the `[tests]` table or path to a test script.
+    pub test: Option<String>,
+    pub processes: Option<u32>,
+    pub search_mode: Option<String>,
+    pub backend: Option<String>,
+    pub scheduling: Option<String>,
+    pub worktree_location: Option<PathBuf>,
+    pub log: Option<PathBuf>,
+    pub interrupt: Option<bool>,
+    pub no_propagate: Option<bool>,
+    pub no_extended: Option<bool>,
+    pub build: Option<PathBuf>,
+    pub build_location: Option<PathBuf>,
+    pub no_cache: Option<bool>,
+    pub worktree_setup: Option<PathBuf>,
+    pub pre_query: Option<PathBuf>,
+    pub post_query: Option<PathBuf>,
+    pub output_tail: Option<usize>,
+    pub diff_output: Option<bool>,
+    pub verdicts: Option<Vec<String>>,
+    pub metric: Option<String>,
+    pub threshold: Option<f64>,
+    pub max_change: Option<f64>,
+    pub higher_is_better: Option<bool>,
+    pub samples: Option<u32>,
+    pub significance: Option<f64>,
+    pub false_positive_rate: Option<f64>,
+    pub false_negative_rate: Option<f64>,
+    pub confidence: Option<f64>,
+    pub find_fix: Option<bool>,
+    pub term_old: Option<String>,
+    pub term_new: Option<String>,
+    /// The command of `test` after resolving it.
+    #[serde(skip)]
+    command: Option<Vec<String>>,
+}
+
+/// A test of the `[tests]` table: a script or a command with its arguments.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum Test {
+    Script(PathBuf),
+    Command(Vec<String>),
+}
+
+impl Test {
+    /// The program and its arguments. Paths of scripts are relative to the
+    /// configuration, a program without a path is looked up in `PATH`.
+    fn command(&self, directory: &Path) -> Vec<String> {
+        match self {
+            Test::Script(path) => vec![directory.join(path).display().to_string()],
+            Test::Command(command) => {
+                let mut command = command.clone();
+                if let Some(program) = command.first_mut() {
+                    if program.contains('/') {
+                        *program = directory.join(&*program).display().to_string();
+                    }
+                }
+                command
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default)]
+    defaults: Settings,
+    #[serde(default)]
+    tests: HashMap<String, Test>,
+    #[serde(default)]
+    profile: HashMap<String, Settings>,
+    #[serde(skip)]
+    directory: PathBuf,
+}
+
+impl Settings {
+    /// Fills all options that are not set with the ones of `other`.
+    fn or(self, other: Settings) -> Settings {
+        Settings {
+            test: self.test.or(other.test),
+            processes: self.processes.or(other.processes),
+            search_mode: self.search_mode.or(other.search_mode),
+            backend: self.backend.or(other.backend),
+            scheduling: self.scheduling.or(other.scheduling),
+            worktree_location: self.worktree_location.or(other.worktree_location),
+            log: self.log.or(other.log),
+            interrupt: self.interrupt.or(other.interrupt),
+            no_propagate: self.no_propagate.or(other.no_propagate),
+            no_extended: self.no_extended.or(other.no_extended),
+            build: self.build.or(other.build),
+            build_location: self.build_location.or(other.build_location),
+            no_cache: self.no_cache.or(other.no_cache),
+            worktree_setup: self.worktree_setup.or(other.worktree_setup),
+            pre_query: self.pre_query.or(other.pre_query),
+            post_query: self.post_query.or(other.post_query),
+            output_tail: self.output_tail.or(other.output_tail),
+            diff_output: self.diff_output.or(other.diff_output),
+            verdicts: self.verdicts.or(other.verdicts),
+            metric: self.metric.or(other.metric),
+            threshold: self.threshold.or(other.threshold),
+            max_change: self.max_change.or(other.max_change),
+            higher_is_better: self.higher_is_better.or(other.higher_is_better),
+            samples: self.samples.or(other.samples),
+            significance: self.significance.or(other.significance),
+            false_positive_rate: self.false_positive_rate.or(other.false_positive_rate),
+            false_negative_rate: self.false_negative_rate.or(other.false_negative_rate),
+            confidence: self.confidence.or(other.confidence),
+            find_fix: self.find_fix.or(other.find_fix),
+            term_old: self.term_old.or(other.term_old),
+            term_new: self.term_new.or(other.term_new),
+            command: self.command.or(other.command),
+        }
+    }
+
+    fn resolve_paths(&mut self, directory: &Path) {
+        for path in [
+            &mut self.worktree_location,
+            &mut self.log,
+            &mut self.build,
+            &mut self.build_location,
+            &mut self.worktree_setup,
+            &mut self.pre_query,
+            &mut self.post_query,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *path = directory.join(&*path);
+        }
+    }
+
+    fn resolve_test(&mut self, tests: &HashMap<String, Vec<String>>, directory: &Path) {
+        if let Some(test) = &self.test {
+            self.command = Some(match tests.get(test) {
+                Some(command) => command.clone(),
+                None => vec![directory.join(test).display().to_string()],
+            });
+        }
+    }
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    let config_home = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_home.join("crs").join("config.toml"))
+}
+
+fn load(path: &Path) -> Result<Option<Config>, String> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let mut config: Config = toml::from_str(&content)
+        .map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+
+    //Scripts are executed in the worktrees, so paths have to be absolute.
+    let directory = path.parent().unwrap_or(Path::new("."));
+    config.directory = fs::canonicalize(directory)
+        .map_err(|e| format!("Couldn't read {}: {}", directory.display(), e))?;
+    config.defaults.resolve_paths(&config.directory);
+    for settings in config.profile.values_mut() {
+        settings.resolve_paths(&config.directory);
+    }
+    for test in config.tests.values_mut() {
+        *test = Test::Command(test.command(&config.directory));
+    }
+    if let Some((name, _)) = config
+        .tests
+        .iter()
+        .find(|(_, test)| matches!(test, Test::Command(command) if command.is_empty()))
+    {
+        return Err(format!(
+            "Invalid config {}: test \"{}\" has no command",
+            path.display(),
+            name
+        ));
+    }
+    Ok(Some(config))
+}
+
+/// Merges the configuration files into the command line arguments. Options
+/// given on the command line always win, followed by the selected profile and
+/// the defaults. The configuration in the repository overrides the one of the
+/// user.
+pub fn apply(args: &mut Args, matches: &ArgMatches) -> Result<(), String> {
+    let repository = args
+        .repository
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let mut configs = vec![];
+    for path in user_config_path()
+        .into_iter()
+        .chain([repository.join(FILE_NAME)])
+    {
+        configs.extend(load(&path)?);
+    }
+
+    let mut tests = HashMap::new();
+    for config in &configs {
+        tests.extend(
+            config
+                .tests
+                .iter()
+                .map(|(name, test)| (name.clone(), test.command(&config.directory))),
+        );
+    }
+
+    //Highest priority first.
+    let mut layers = vec![];
+    if let Some(name) = &args.profile {
+        for config in configs.iter().rev() {
+            if let Some(profile) = config.profile.get(name) {
+                layers.push((profile.clone(), &config.directory));
+            }
+        }
+        if layers.is_empty() {
+            return Err(format!("Profile \"{}\" not found!", name));
+        }
+    }
+    for config in configs.iter().rev() {
+        layers.push((config.defaults.clone(), &config.directory));
+    }
+
+    let settings = layers
+        .into_iter()
+        .map(|(mut settings, directory)| {
+            settings.resolve_test(&tests, directory);
+            settings
+        })
+        .fold(Settings::default(), Settings::or);
+
+    args.repository = Some(repository);
+    let command = match args.test.take() {
+        Some(test) => match tests.get(&test.display().to_string()) {
+            Some(command) => Some(command.clone()),
+            None => Some(vec![test.display().to_string()]),
+        },
+        None => settings.command,
+    };
+    if let Some((program, arguments)) = command.as_deref().and_then(<[String]>::split_first) {
+        args.test = Some(PathBuf::from(program));
+        args.test_arguments = arguments.to_vec();
+    }
+
+    let from_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    if !from_command_line("processes") {
+        args.processes = settings.processes.unwrap_or(args.processes);
+    }
+    if !from_command_line("output-tail") {
+        args.output_tail = settings.output_tail.unwrap_or(args.output_tail);
+    }
+    if !from_command_line("samples") {
+        args.samples = settings.samples.unwrap_or(args.samples);
+    }
+    if !from_command_line("significance") {
+        args.significance = settings.significance.unwrap_or(args.significance);
+    }
+    if !from_command_line("false-positive-rate") {
+        args.false_positive_rate = settings
+            .false_positive_rate
+            .unwrap_or(args.false_positive_rate);
+    }
+    if !from_command_line("false-negative-rate") {
+        args.false_negative_rate = settings
+            .false_negative_rate
+            .unwrap_or(args.false_negative_rate);
+    }
+    if !from_command_line("confidence") {
+        args.confidence = settings.confidence.unwrap_or(args.confidence);
+    }
+    if !from_command_line("search-mode") {
+        args.search_mode = settings.search_mode.unwrap_or(args.search_mode.clone());
+    }
+    if !from_command_line("backend") {
+        args.backend = settings.backend.unwrap_or(args.backend.clone());
+    }
+    if !["git", "gitoxide"].contains(&args.backend.as_str()) {
+        return Err(format!("Backend \"{}\" not found!", args.backend));
+    }
+    if !from_command_line("scheduling") {
+        args.scheduling = settings.scheduling.unwrap_or(args.scheduling.clone());
+    }
+    args.scheduling.parse::<Scheduling>()?;
+
+    args.worktree_location = args.worktree_location.take().or(settings.worktree_location);
+    args.log = args.log.take().or(settings.log);
+    args.build = args.build.take().or(settings.build);
+    args.build_location = args.build_location.take().or(settings.build_location);
+    args.worktree_setup = args.worktree_setup.take().or(settings.worktree_setup);
+    args.pre_query = args.pre_query.take().or(settings.pre_query);
+    args.post_query = args.post_query.take().or(settings.post_query);
+
+    args.metric = args.metric.take().or(settings.metric);
+    args.threshold = args.threshold.or(settings.threshold);
+    args.max_change = args.max_change.or(settings.max_change);
+    args.term_old = args.term_old.take().or(settings.term_old);
+    args.term_new = args.term_new.take().or(settings.term_new);
+
+    if args.verdicts.is_empty() {
+        args.verdicts = settings.verdicts.unwrap_or_default();
+    }
+
+    args.interrupt = flag(args.interrupt, args.no_interrupt, settings.interrupt);
+    args.no_propagate = flag(args.no_propagate, args.propagate, settings.no_propagate);
+    args.no_extended = flag(args.no_extended, args.extended, settings.no_extended);
+    args.no_cache = flag(args.no_cache, args.cache, settings.no_cache);
+    args.diff_output = flag(args.diff_output, args.no_diff_output, settings.diff_output);
+    args.higher_is_better = flag(
+        args.higher_is_better,
+        args.no_higher_is_better,
+        settings.higher_is_better,
+    );
+    args.find_fix = flag(args.find_fix, args.no_find_fix, settings.find_fix);
+
+    Ok(())
+}
+
+//A flag or its negation on the command line wins over the configuration.
+fn flag(set: bool, unset: bool, setting: Option<bool>) -> bool {
+    set || (!unset && setting.unwrap_or(false))
+}
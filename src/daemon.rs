@@ -0,0 +1,286 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use clap::CommandFactory;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{manage::Control, terms::Terms, Args};
+
+/// Socket of the daemon, if no other is given.
+pub fn default_socket() -> PathBuf {
+    std::env::temp_dir().join("crs_daemon.sock")
+}
+
+/// Requests of the control socket, one JSON object per line. Each request is
+/// answered with one line, `{"ok": true, ...}` or `{"ok": false, "error": ...}`.
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case", deny_unknown_fields)]
+enum Request {
+    /// Starts a search with the same arguments as the command line, e.g.
+    /// `["/repo", "/repo/test.sh", "-s", "<source hash>", "-t", "<target hash>"]`.
+    Start {
+        arguments: Vec<String>,
+    },
+    List,
+    Status {
+        id: u32,
+    },
+    Processes {
+        id: u32,
+        processes: u32,
+    },
+    /// Adds the result of a commit in the terms of the search, e.g. `"good"`,
+    /// `"bad"` or `"skip"`.
+    Mark {
+        id: u32,
+        commit: String,
+        result: String,
+    },
+    Cancel {
+        id: u32,
+    },
+}
+
+struct Entry {
+    arguments: Vec<String>,
+    repository: String,
+    terms: Terms,
+    commits: Vec<String>,
+    control: Control,
+    handle: JoinHandle<bool>,
+}
+
+impl Entry {
+    fn state(&self) -> &'static str {
+        let status = self.control.status();
+        match (self.handle.is_finished(), status.running) {
+            //A search that stops without updating its status has panicked.
+            (true, true) => "failed",
+            (false, _) => "running",
+            (true, false) if status.cancelled => "cancelled",
+            (true, false) => "finished",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Daemon {
+    searches: BTreeMap<u32, Entry>,
+    /// Repositories of searches that are being prepared.
+    preparing: BTreeSet<String>,
+    next_id: u32,
+}
+
+type Shared = Arc<Mutex<Daemon>>;
+
+/// Runs searches in the background, they are controlled via the socket.
+pub fn run(socket: &Path) {
+    //Only the socket of a daemon that stopped without clean up is replaced.
+    if UnixStream::connect(socket).is_ok() {
+        eprintln!(
+            "Another daemon is already listening on {}",
+            socket.display()
+        );
+        std::process::exit(1);
+    }
+    let _ = fs::remove_file(socket);
+    let listener = match UnixListener::bind(socket) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("Couldn't listen on {}: {}", socket.display(), err);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("Listening on {}", socket.display());
+
+    let daemon = Shared::default();
+    stop_on_signal(daemon.clone(), socket.to_path_buf());
+
+    for stream in listener.incoming().flatten() {
+        let daemon = daemon.clone();
+        thread::spawn(move || serve(stream, daemon));
+    }
+}
+
+fn serve(stream: UnixStream, daemon: Shared) {
+    let reader = BufReader::new(&stream);
+    for line in reader.lines().map_while(Result::ok) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => handle(request, &daemon),
+            Err(err) => Err(format!("invalid request: {}", err)),
+        };
+        let reply = match reply {
+            Ok(Value::Object(mut object)) => {
+                object.insert("ok".to_string(), Value::Bool(true));
+                Value::Object(object)
+            }
+            Ok(_) => json!({ "ok": true }),
+            Err(err) => json!({ "ok": false, "error": err }),
+        };
+        if writeln!(&stream, "{}", reply).is_err() {
+            break;
+        }
+    }
+}
+
+fn handle(request: Request, daemon: &Shared) -> Result<Value, String> {
+    match request {
+        Request::Start { arguments } => start(arguments, daemon),
+        Request::List => {
+            let daemon = daemon.lock().unwrap();
+            let searches = daemon
+                .searches
+                .iter()
+                .map(|(id, entry)| {
+                    json!({
+                        "id": id,
+                        "state": entry.state(),
+                        "arguments": entry.arguments,
+                    })
+                })
+                .collect::<Vec<_>>();
+            Ok(json!({ "searches": searches }))
+        }
+        Request::Status { id } => with_search(daemon, id, |entry| {
+            Ok(json!({
+                "state": entry.state(),
+                "status": entry.control.status(),
+            }))
+        }),
+        Request::Processes { id, processes } => with_search(daemon, id, |entry| {
+            if processes == 0 {
+                return Err("a search needs at least one process".to_string());
+            }
+            entry.control.set_processes(processes);
+            Ok(Value::Null)
+        }),
+        Request::Mark { id, commit, result } => with_search(daemon, id, |entry| {
+            let result = entry.terms.parse(&result)?;
+            let commit = crate::resolve(&commit, &entry.commits)?;
+            entry.control.add_result(commit.clone(), result);
+            Ok(json!({ "commit": commit }))
+        }),
+        Request::Cancel { id } => with_search(daemon, id, |entry| {
+            entry.control.cancel();
+            Ok(Value::Null)
+        }),
+    }
+}
+
+fn with_search(
+    daemon: &Shared,
+    id: u32,
+    f: impl FnOnce(&Entry) -> Result<Value, String>,
+) -> Result<Value, String> {
+    let daemon = daemon.lock().unwrap();
+    match daemon.searches.get(&id) {
+        Some(entry) => f(entry),
+        None => Err(format!("there is no search {}", id)),
+    }
+}
+
+fn start(arguments: Vec<String>, daemon: &Shared) -> Result<Value, String> {
+    let matches = Args::command()
+        .try_get_matches_from(std::iter::once("crs".to_string()).chain(arguments.clone()))
+        .map_err(|err| err.to_string())?;
+    if matches.subcommand().is_some() {
+        return Err("only searches can be started".to_string());
+    }
+
+    let mut args = crate::arguments(&matches).map_err(|err| err.to_string())?;
+    //Other paths of the same repository, e.g. with a trailing slash or via a
+    //symlink, must not bypass the check below.
+    let repository = args.repository.as_ref().unwrap();
+    let repository = fs::canonicalize(repository)
+        .map_err(|err| format!("Couldn't open {}: {}", repository.display(), err))?;
+    args.repository = Some(repository.clone());
+    let repository = repository.display().to_string();
+    {
+        let mut daemon = daemon.lock().unwrap();
+        let busy = daemon.preparing.contains(&repository)
+            || daemon
+                .searches
+                .values()
+                .any(|entry| entry.repository == repository && !entry.handle.is_finished());
+        if busy {
+            //The worktrees of both searches would have the same names.
+            return Err(format!(
+                "there is already a search running in {}",
+                repository
+            ));
+        }
+        daemon.preparing.insert(repository.clone());
+    }
+
+    //Preparing the search can take a while for large repositories, the lock is
+    //only held to reserve the repository and to register the search.
+    let search = crate::prepare(args);
+    let mut daemon = daemon.lock().unwrap();
+    daemon.preparing.remove(&repository);
+    let search = search.map_err(|err| err.to_string())?;
+
+    let id = daemon.next_id;
+    daemon.next_id += 1;
+    let control = Control::default();
+    let entry = Entry {
+        arguments,
+        repository,
+        terms: search.terms().clone(),
+        commits: search.commits.clone(),
+        control: control.clone(),
+        handle: thread::spawn(move || {
+            search.run(&control).unwrap_or_else(|err| {
+                eprintln!("Search {} failed: {}", id, err);
+                false
+            })
+        }),
+    };
+    daemon.searches.insert(id, entry);
+    eprintln!("Started search {}", id);
+    Ok(json!({ "id": id }))
+}
+
+//The first SIGINT/SIGTERM cancels all searches and stops the daemon, after
+//their worktrees are removed. A second signal exits immediately.
+fn stop_on_signal(daemon: Shared, socket: PathBuf) {
+    let stopping = Arc::new(AtomicBool::new(false));
+    let handler = ctrlc::set_handler(move || {
+        if stopping.swap(true, Ordering::SeqCst) {
+            eprintln!("Exit without clean up!");
+            std::process::exit(130);
+        }
+        eprintln!("Stop daemon, press Ctrl-C again to exit immediately!");
+
+        let daemon = daemon.clone();
+        let socket = socket.clone();
+        thread::spawn(move || {
+            let searches = std::mem::take(&mut daemon.lock().unwrap().searches);
+            for entry in searches.values() {
+                entry.control.cancel();
+            }
+            for (_, entry) in searches {
+                let _ = entry.handle.join();
+            }
+            let _ = fs::remove_file(socket);
+            std::process::exit(130);
+        });
+    });
+
+    if let Err(err) = handler {
+        eprintln!("Couldn't register signal handler: {}", err);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::dvcs::DVCS;
-use crate::graph::{prune_downwards, Adag};
+use crate::graph::{Adag, Oid};
 use daggy::{Dag, NodeIndex};
 use std::cmp::Ordering;
 use std::collections::hash_map::DefaultHasher;
@@ -7,7 +7,7 @@ use std::hash::Hasher;
 use std::process::{Command, Output};
 use std::{collections::HashMap, hash::Hash};
 
-use super::{run_command_sync, Worktree};
+use super::{run_command_sync, search_graph, Worktree};
 
 #[derive(Debug, Clone)]
 pub struct Git;
@@ -17,9 +17,9 @@ impl DVCS for Git {
         repository: &str,
         sources: Vec<String>,
         targets: Vec<String>,
-    ) -> Result<Adag<String, ()>, ()> {
-        let mut graph = Dag::<String, ()>::new();
-        let mut indexation = HashMap::<String, NodeIndex>::new();
+    ) -> Result<Adag<()>, ()> {
+        let mut graph = Dag::<Oid, ()>::new();
+        let mut indexation = HashMap::<Oid, NodeIndex>::new();
 
         let lca = match sources.len().cmp(&1) {
             Ordering::Greater => {
@@ -48,30 +48,9 @@ impl DVCS for Git {
             .arg(lca?);
 
         let rev_list = handle_result(run_command_sync(repository, &mut rev_command));
-        add_rev_list(&mut graph, &mut indexation, rev_list?);
-
-        let source_indices = sources
-            .iter()
-            .filter_map(|h| indexation.get(h).cloned())
-            .collect::<Vec<_>>();
-        let (pruned_graph, pruned_indexation) = prune_downwards(&graph, &source_indices);
-        let remaining_sources = sources
-            .iter()
-            .filter(|h| pruned_indexation.contains_key(*h))
-            .cloned()
-            .collect();
-        let remaining_targets = targets
-            .iter()
-            .filter(|h| pruned_indexation.contains_key(*h))
-            .cloned()
-            .collect();
+        add_rev_list(&mut graph, &mut indexation, rev_list?).map_err(|err| print_error(&err))?;
 
-        Ok(Adag {
-            graph: pruned_graph,
-            indexation: pruned_indexation,
-            sources: remaining_sources,
-            targets: remaining_targets,
-        })
+        search_graph(&graph, &indexation, sources, targets).map_err(|err| print_error(&err))
     }
 
     fn create_worktree(
@@ -125,11 +104,13 @@ impl DVCS for Git {
 
     fn remove_worktree(worktree: &Worktree) -> Result<(), ()> {
         let mut rm_tree = Command::new("git");
-        rm_tree.args(["worktree", "remove", worktree.name.as_str()]);
+        //Git refuses to remove worktrees with submodules, e.g. initialised by a
+        //setup hook. The worktree is cleaned before anyway.
+        rm_tree.args(["worktree", "remove", "--force", worktree.name.as_str()]);
 
         worktree_clean(worktree);
 
-        return match run_command_sync(&worktree.location, &mut rm_tree) {
+        match run_command_sync(&worktree.location, &mut rm_tree) {
             Ok(o) => {
                 if o.status.success() {
                     Ok(())
@@ -142,7 +123,38 @@ impl DVCS for Git {
                 print_error(e.to_string().as_str());
                 Err(())
             }
-        };
+        }
+    }
+
+    fn list_worktrees(repository: &str) -> Result<Vec<Worktree>, ()> {
+        let mut command = Command::new("git");
+        command.args(["worktree", "list", "--porcelain"]);
+
+        let response = handle_result(run_command_sync(repository, &mut command))?;
+        let worktrees = response
+            .lines()
+            .filter_map(|line| line.strip_prefix("worktree "))
+            .filter_map(|location| {
+                let name = location.rsplit('/').next()?;
+                if is_crs_worktree(name) {
+                    Some(Worktree {
+                        location: location.to_string(),
+                        name: name.to_string(),
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Ok(worktrees)
+    }
+
+    fn prune_worktrees(repository: &str) -> Result<(), ()> {
+        let mut command = Command::new("git");
+        command.args(["worktree", "prune"]);
+
+        handle_result(run_command_sync(repository, &mut command)).map(|_| ())
     }
 
     fn checkout(worktree: &Worktree, commit: &str) -> Result<(), ()> {
@@ -151,7 +163,7 @@ impl DVCS for Git {
 
         worktree_clean(worktree);
 
-        return match run_command_sync(&worktree.location, &mut command) {
+        match run_command_sync(&worktree.location, &mut command) {
             Ok(output) => {
                 if output.status.success() {
                     Ok(())
@@ -164,7 +176,7 @@ impl DVCS for Git {
                 print_error(e.to_string().as_str());
                 Err(())
             }
-        };
+        }
     }
 
     fn get_commit_info(repository: &str, commit: &str) -> Option<String> {
@@ -204,6 +216,67 @@ impl DVCS for Git {
             }
         }
     }
+
+    fn tree_hash(repository: &str, commit: &str) -> Option<String> {
+        let mut command = Command::new("git");
+        command.args(["rev-parse", &format!("{}^{{tree}}", commit)]);
+
+        handle_result(run_command_sync(repository, &mut command)).ok()
+    }
+
+    fn parents(repository: &str, commit: &str) -> Option<Vec<String>> {
+        let mut command = Command::new("git");
+        command.args(["rev-list", "--parents", "-n", "1", commit]);
+
+        let line = handle_result(run_command_sync(repository, &mut command)).ok()?;
+        Some(
+            line.split_whitespace()
+                .skip(1)
+                .map(|p| p.to_string())
+                .collect(),
+        )
+    }
+
+    fn rev_list(repository: &str, revision: &str) -> Result<Vec<String>, String> {
+        let mut command = Command::new("git");
+        if revision.contains("..") {
+            command.args(["rev-list", revision, "--"]);
+        } else {
+            command.args(["rev-parse", "--verify", "--quiet"]);
+            command.arg(format!("{}^{{commit}}", revision));
+        }
+
+        let output = run_command_sync(repository, &mut command).map_err(|err| err.to_string())?;
+        if !output.status.success() {
+            return Err(format!("{} is not a commit or a range", revision));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|hash| hash.to_string())
+            .collect())
+    }
+}
+
+//Matches the names of `create_worktree`: `crs_<id>` or `<hash>_crs_<id>` for
+//external locations.
+fn is_crs_worktree(name: &str) -> bool {
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    let id = match name.rsplit_once("crs_") {
+        Some((prefix, id)) => {
+            let valid_prefix = match prefix.strip_suffix('_') {
+                Some(hash) => is_number(hash),
+                None => prefix.is_empty(),
+            };
+            if !valid_prefix {
+                return false;
+            }
+            id
+        }
+        None => return false,
+    };
+
+    is_number(id)
 }
 
 fn worktree_clean(worktree: &Worktree) {
@@ -249,47 +322,40 @@ fn print_error(msg: &str) {
 }
 
 fn add_rev_list(
-    graph: &mut Dag<String, ()>,
-    indexation: &mut HashMap<String, NodeIndex>,
+    graph: &mut Dag<Oid, ()>,
+    indexation: &mut HashMap<Oid, NodeIndex>,
     rev_list: String,
-) {
-    let lines = rev_list.lines();
+) -> Result<(), String> {
+    let mut edges = vec![];
 
-    for line in lines {
+    for line in rev_list.lines() {
         let mut hashes = line.split(' ');
-        let op_h1 = hashes.next();
-        let op_h2 = hashes.next();
-
-        let index1 = try_add_hash(op_h1, graph, indexation);
-        let mut index2 = try_add_hash(op_h2, graph, indexation);
 
-        if let Some(i1) = index1 {
-            while index2.is_some() {
-                if graph.update_edge(index2.unwrap(), i1, ()).is_err() {
-                    panic!("Error while parsing commit graph from git!");
-                }
-                index2 = try_add_hash(hashes.next(), graph, indexation);
+        if let Some(child) = try_add_hash(hashes.next(), graph, indexation)? {
+            while let Some(parent) = try_add_hash(hashes.next(), graph, indexation)? {
+                edges.push((parent, child, ()));
             }
         }
     }
+
+    //Checking for cycles once is much faster than for each edge.
+    graph
+        .add_edges(edges)
+        .map_err(|_| "the commit graph has a cycle".to_string())?;
+    Ok(())
 }
 
 fn try_add_hash(
     op_hash: Option<&str>,
-    dag: &mut Dag<String, ()>,
-    added: &mut HashMap<String, NodeIndex>,
-) -> Option<NodeIndex> {
-    let hash = op_hash?;
-
-    if !added.contains_key(hash) {
-        let index = dag.add_node(String::from(hash));
-        added.insert(String::from(hash), index);
-
-        Some(index)
-    } else {
-        //UNWRAP: We checked before, that added has this key.
-        Some(*added.get(hash).unwrap())
-    }
+    dag: &mut Dag<Oid, ()>,
+    added: &mut HashMap<Oid, NodeIndex>,
+) -> Result<Option<NodeIndex>, String> {
+    let oid = match op_hash.filter(|hash| !hash.is_empty()) {
+        Some(hash) => hash.parse::<Oid>()?,
+        None => return Ok(None),
+    };
+
+    Ok(Some(*added.entry(oid).or_insert_with(|| dag.add_node(oid))))
 }
 
 fn handle_result(res: std::io::Result<Output>) -> Result<String, ()> {
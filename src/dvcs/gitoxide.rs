@@ -0,0 +1,208 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use chrono::{FixedOffset, TimeZone};
+use daggy::{Dag, NodeIndex};
+use gix::{ObjectId, Repository};
+
+use crate::dvcs::DVCS;
+use crate::graph::{Adag, Oid};
+
+use super::{git::Git, search_graph, Worktree};
+
+/// Reads commits directly from the object database (and the commit-graph file,
+/// if there is one) instead of spawning `git`. Worktrees are still managed by
+/// `git`.
+#[derive(Debug, Clone)]
+pub struct Gitoxide;
+
+impl DVCS for Gitoxide {
+    fn commit_graph(
+        repository: &str,
+        sources: Vec<String>,
+        targets: Vec<String>,
+    ) -> Result<Adag<()>, ()> {
+        let repo = open(repository)?;
+
+        let source_ids = sources
+            .iter()
+            .map(|source| resolve(&repo, source))
+            .collect::<Result<Vec<_>, _>>()?;
+        let target_ids = targets
+            .iter()
+            .map(|target| resolve(&repo, target))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        //The graph only knows full hashes, not the names of revisions.
+        let sources = source_ids.iter().map(|id| id.to_string()).collect();
+        let targets = target_ids.iter().map(|id| id.to_string()).collect();
+
+        let lca = match source_ids.len() {
+            0 => {
+                print_error("Missing source!");
+                return Err(());
+            }
+            1 => source_ids[0],
+            _ => repo
+                .merge_base_octopus(source_ids)
+                .map_err(|err| print_error(&err.to_string()))?
+                .detach(),
+        };
+
+        let walk = repo
+            .rev_walk(target_ids)
+            .with_hidden([lca])
+            .all()
+            .map_err(|err| print_error(&err.to_string()))?;
+
+        let mut graph = Dag::<Oid, ()>::new();
+        let mut indexation = HashMap::<Oid, NodeIndex>::new();
+        let mut add = |id: &ObjectId| {
+            let oid = Oid::from(id_bytes(id).map_err(|err| print_error(&err))?);
+            Ok(*indexation.entry(oid).or_insert_with(|| graph.add_node(oid)))
+        };
+
+        let mut edges = vec![];
+        for info in walk {
+            let info = info.map_err(|err| print_error(&err.to_string()))?;
+            let child = add(&info.id)?;
+            for parent in &info.parent_ids {
+                edges.push((add(parent)?, child, ()));
+            }
+        }
+
+        //Checking for cycles once is much faster than for each edge.
+        graph
+            .add_edges(edges)
+            .map_err(|_| print_error("the commit graph has a cycle"))?;
+
+        search_graph(&graph, &indexation, sources, targets).map_err(|err| print_error(&err))
+    }
+
+    fn create_worktree(
+        repository: &str,
+        name: &str,
+        external_location: Option<String>,
+    ) -> Result<Worktree, ()> {
+        Git::create_worktree(repository, name, external_location)
+    }
+
+    fn remove_worktree(worktree: &Worktree) -> Result<(), ()> {
+        Git::remove_worktree(worktree)
+    }
+
+    fn list_worktrees(repository: &str) -> Result<Vec<Worktree>, ()> {
+        Git::list_worktrees(repository)
+    }
+
+    fn prune_worktrees(repository: &str) -> Result<(), ()> {
+        Git::prune_worktrees(repository)
+    }
+
+    fn checkout(worktree: &Worktree, commit: &str) -> Result<(), ()> {
+        Git::checkout(worktree, commit)
+    }
+
+    //Same format as `git log --pretty=reference`.
+    fn get_commit_info(repository: &str, commit: &str) -> Option<String> {
+        let repo = open(repository).ok()?;
+        let id = resolve(&repo, commit).ok()?;
+        let object = repo
+            .find_commit(id)
+            .map_err(|err| print_error(&err.to_string()))
+            .ok()?;
+
+        let summary = object.message().ok()?.summary().to_string();
+        let time = object.author().ok()?.time().ok()?;
+        let date = FixedOffset::east_opt(time.offset)
+            .and_then(|offset| offset.timestamp_opt(time.seconds, 0).single())?
+            .format("%Y-%m-%d");
+
+        Some(format!(
+            "{} ({}, {})\n",
+            object.id().shorten_or_id(),
+            summary,
+            date
+        ))
+    }
+
+    fn tree_hash(repository: &str, commit: &str) -> Option<String> {
+        let repo = open(repository).ok()?;
+        let id = resolve(&repo, commit).ok()?;
+        let tree = repo.find_commit(id).ok()?.tree_id().ok()?;
+        Some(tree.to_string())
+    }
+
+    fn parents(repository: &str, commit: &str) -> Option<Vec<String>> {
+        let repo = open(repository).ok()?;
+        let id = resolve(&repo, commit).ok()?;
+        let object = repo.find_commit(id).ok()?;
+        Some(object.parent_ids().map(|p| p.to_string()).collect())
+    }
+
+    fn rev_list(repository: &str, revision: &str) -> Result<Vec<String>, String> {
+        let repo = open(repository).map_err(|_| format!("Couldn't open {}", repository))?;
+        //An empty side of a range is HEAD, like for `git rev-list`.
+        let parse = |revision: &str| {
+            let revision = if revision.is_empty() {
+                "HEAD"
+            } else {
+                revision
+            };
+            repo.rev_parse_single(format!("{}^{{commit}}", revision).as_str())
+                .map(|id| id.detach())
+                .map_err(|err| err.to_string())
+        };
+
+        match revision.split_once("..") {
+            Some((from, to)) => repo
+                .rev_walk([parse(to)?])
+                .with_hidden([parse(from)?])
+                .all()
+                .map_err(|err| err.to_string())?
+                .map(|info| {
+                    info.map(|info| info.id.to_string())
+                        .map_err(|err| err.to_string())
+                })
+                .collect(),
+            None => Ok(vec![parse(revision)?.to_string()]),
+        }
+    }
+}
+
+thread_local! {
+    //Repositories are discovered once per thread, not for each commit that is
+    //looked up. They can only be shared between threads with the `parallel`
+    //feature of gix.
+    static REPOSITORIES: RefCell<HashMap<String, Repository>> = RefCell::new(HashMap::new());
+}
+
+fn open(repository: &str) -> Result<Repository, ()> {
+    REPOSITORIES.with(|repositories| {
+        if let Some(repo) = repositories.borrow().get(repository) {
+            return Ok(repo.clone());
+        }
+        let repo = gix::discover(repository).map_err(|err| print_error(&err.to_string()))?;
+        repositories
+            .borrow_mut()
+            .insert(repository.to_string(), repo.clone());
+        Ok(repo)
+    })
+}
+
+//Any revision that names a commit, like for `git rev-parse`.
+fn resolve(repo: &Repository, revision: &str) -> Result<ObjectId, ()> {
+    repo.rev_parse_single(format!("{}^{{commit}}", revision).as_str())
+        .map(|id| id.detach())
+        .map_err(|err| print_error(&err.to_string()))
+}
+
+//Only SHA-1 repositories are supported, like by the git backend.
+fn id_bytes(id: &ObjectId) -> Result<[u8; 20], String> {
+    id.as_bytes()
+        .try_into()
+        .map_err(|_| format!("{} is not a SHA-1 hash", id))
+}
+
+fn print_error(msg: &str) {
+    eprintln!("Gitoxide Error: {}", msg);
+}
@@ -1,27 +1,74 @@
+use daggy::{Dag, NodeIndex};
 use std::{
+    collections::HashMap,
     fs::File,
     io::Error,
     process::{Child, Command, Output, Stdio},
 };
 
-use crate::graph::Adag;
+use crate::graph::{prune_downwards, Adag, Oid};
 
 pub mod git;
+pub mod gitoxide;
 
+#[allow(clippy::upper_case_acronyms)]
 pub trait DVCS {
     fn commit_graph(
         repository: &str,
         start: Vec<String>,
         targets: Vec<String>,
-    ) -> Result<Adag<String, ()>, ()>;
+    ) -> Result<Adag<()>, ()>;
     fn create_worktree(
         repository: &str,
         name: &str,
         external_location: Option<String>,
     ) -> Result<Worktree, ()>;
     fn remove_worktree(worktree: &Worktree) -> Result<(), ()>;
+    fn list_worktrees(repository: &str) -> Result<Vec<Worktree>, ()>;
+    fn prune_worktrees(repository: &str) -> Result<(), ()>;
     fn checkout(worktree: &Worktree, commit: &str) -> Result<(), ()>;
     fn get_commit_info(repository: &str, commit: &str) -> Option<String>;
+    fn tree_hash(repository: &str, commit: &str) -> Option<String>;
+    fn parents(repository: &str, commit: &str) -> Option<Vec<String>>;
+    /// Full hashes of the commit of a revision, or of the commits of a range
+    /// `<from>..<to>` like for `git rev-list`.
+    fn rev_list(repository: &str, revision: &str) -> Result<Vec<String>, String>;
+}
+
+/// Prunes the commits that were read by a backend to the descendants of the
+/// sources. Sources and targets outside of the graph are dropped, but at least
+/// one of each has to remain.
+pub fn search_graph(
+    graph: &Dag<Oid, ()>,
+    indexation: &HashMap<Oid, NodeIndex>,
+    sources: Vec<String>,
+    targets: Vec<String>,
+) -> Result<Adag<()>, String> {
+    let index = |hash: &String| {
+        hash.parse::<Oid>()
+            .ok()
+            .and_then(|oid| indexation.get(&oid))
+            .cloned()
+    };
+    let source_indices = sources.iter().filter_map(index).collect::<Vec<_>>();
+    let mut adag = Adag::new(prune_downwards(graph, &source_indices), vec![], vec![]);
+
+    adag.sources = sources
+        .into_iter()
+        .filter(|h| adag.get_index(h).is_some())
+        .collect();
+    adag.targets = targets
+        .into_iter()
+        .filter(|h| adag.get_index(h).is_some())
+        .collect();
+
+    if adag.sources.is_empty() {
+        return Err("none of the sources is part of the commit graph".to_string());
+    }
+    if adag.targets.is_empty() {
+        return Err("none of the targets is a descendant of the sources".to_string());
+    }
+    Ok(adag)
 }
 
 #[derive(Debug, Clone)]
@@ -33,11 +80,20 @@ pub struct Worktree {
 pub fn run_script_async(
     location: &str,
     script_path: &str,
+    arguments: &[String],
     log_stdout: Option<std::path::PathBuf>,
     log_stderr: Option<std::path::PathBuf>,
+    envs: &[(String, String)],
 ) -> Result<Child, Error> {
     let mut command = Command::new(script_path);
+    command.args(arguments);
     command.current_dir(location);
+    command.envs(envs.iter().cloned());
+
+    //A separate process group keeps signals of the terminal (e.g. Ctrl-C)
+    //away from the script and lets us stop everything it spawned.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
 
     match log_stdout {
         Some(path) => {
@@ -2,44 +2,195 @@ use daggy::{petgraph::visit::IntoNodeIdentifiers, Dag, NodeIndex, Walker};
 use std::{
     cmp::{max, min},
     collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    str::FromStr,
+    sync::Arc,
 };
 
-#[derive(Debug, Clone)]
-pub struct Adag<N, E> {
+/// Id of a commit, the SHA-1 hash in binary. Takes 20 bytes instead of a heap
+/// allocated string of 40 hex digits.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Oid([u8; 20]);
+
+impl FromStr for Oid {
+    type Err = String;
+
+    fn from_str(hash: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{} is not a SHA-1 hash", hash);
+        //from_str_radix would accept a sign as well.
+        if hash.len() != 40 || !hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0; 20];
+        for (byte, digits) in bytes.iter_mut().zip(hash.as_bytes().chunks(2)) {
+            //UNWRAP: The hash only contains ASCII characters.
+            let digits = std::str::from_utf8(digits).unwrap();
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| invalid())?;
+        }
+        Ok(Oid(bytes))
+    }
+}
+
+impl From<[u8; 20]> for Oid {
+    fn from(bytes: [u8; 20]) -> Self {
+        Oid(bytes)
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// Commit graph of a search. The structure never changes and is shared by all
+/// clones, results are kept by the algorithms.
+#[derive(Debug)]
+pub struct Adag<E> {
     pub sources: Vec<String>,
     pub targets: Vec<String>,
-    pub graph: Dag<N, E>,
-    pub indexation: HashMap<String, NodeIndex>,
+    pub graph: Arc<Dag<Oid, E>>,
+    indexation: Arc<HashMap<Oid, NodeIndex>>,
 }
 
-impl<N: Clone, E: Clone> Adag<N, E> {
-    pub fn node(&self, hash: &String) -> N {
-        self.graph
-            .node_weight(self.index(hash))
-            .expect("Radag seems corrupted!")
-            .clone()
+impl<E> Clone for Adag<E> {
+    fn clone(&self) -> Self {
+        Adag {
+            sources: self.sources.clone(),
+            targets: self.targets.clone(),
+            graph: self.graph.clone(),
+            indexation: self.indexation.clone(),
+        }
     }
+}
 
-    pub fn node_from_index(&self, index: NodeIndex) -> N {
-        self.graph
-            .node_weight(index)
-            .expect("Radag seems corrupted!")
-            .clone()
+impl<E> Adag<E> {
+    pub fn new(graph: Dag<Oid, E>, sources: Vec<String>, targets: Vec<String>) -> Self {
+        let indexation = graph
+            .node_identifiers()
+            .map(|index| (graph[index], index))
+            .collect();
+
+        Adag {
+            sources,
+            targets,
+            graph: Arc::new(graph),
+            indexation: Arc::new(indexation),
+        }
     }
 
-    pub fn index(&self, hash: &String) -> NodeIndex {
-        *self
-            .indexation
-            .get(hash)
+    pub fn index(&self, hash: &str) -> NodeIndex {
+        self.get_index(hash)
             .unwrap_or_else(|| panic!("{} is not a node in the graph!", hash))
     }
 
+    pub fn get_index(&self, hash: &str) -> Option<NodeIndex> {
+        let oid = hash.parse::<Oid>().ok()?;
+        self.indexation.get(&oid).copied()
+    }
+
     pub fn hash_from_index(&self, index: NodeIndex) -> String {
-        let (k, _) = self.indexation.iter().find(|(_, v)| v == &&index).unwrap();
-        k.clone()
+        self.graph
+            .node_weight(index)
+            .expect("Adag seems corrupted!")
+            .to_string()
+    }
+
+    pub fn hashes(&self) -> impl Iterator<Item = String> + '_ {
+        self.indexation.keys().map(|oid| oid.to_string())
     }
 }
 
+/// Narrows down the regression point of `target`. Starting at the target, we
+/// move to invalid ancestors as long as they aren't ancestors of a valid node.
+/// Returns the last valid nodes and the first invalid node.
+pub fn narrow_interval<E>(
+    graph: &Adag<E>,
+    valid_nodes: &HashSet<NodeIndex>,
+    is_invalid: impl Fn(NodeIndex) -> bool,
+    target: NodeIndex,
+) -> (Vec<NodeIndex>, NodeIndex) {
+    let valid = valid_closure(graph, valid_nodes);
+
+    let mut first_bad = target;
+    loop {
+        let mut last_good = HashSet::new();
+        let mut next_bad = None;
+        let mut visited = HashSet::from([first_bad]);
+        let mut queue = VecDeque::from([first_bad]);
+
+        'search: while let Some(current) = queue.pop_front() {
+            for (_, parent) in graph.graph.parents(current).iter(&graph.graph) {
+                if valid.contains(&parent) {
+                    last_good.insert(parent);
+                } else if visited.insert(parent) {
+                    if is_invalid(parent) {
+                        next_bad = Some(parent);
+                        break 'search;
+                    }
+                    queue.push_back(parent);
+                }
+            }
+        }
+
+        match next_bad {
+            Some(bad) => first_bad = bad,
+            None => return (Vec::from_iter(last_good), first_bad),
+        }
+    }
+}
+
+/// Skipped ancestors of a regression point that could be the regression point
+/// as well: all ancestors that are connected to it via skipped nodes and that
+/// aren't ancestors of a valid node.
+pub fn skipped_ancestors<E>(
+    graph: &Adag<E>,
+    valid_nodes: &HashSet<NodeIndex>,
+    is_skipped: impl Fn(NodeIndex) -> bool,
+    regression: NodeIndex,
+) -> Vec<NodeIndex> {
+    let valid = valid_closure(graph, valid_nodes);
+
+    let mut skipped = vec![];
+    let mut visited = HashSet::from([regression]);
+    let mut queue = VecDeque::from([regression]);
+
+    while let Some(current) = queue.pop_front() {
+        for (_, parent) in graph.graph.parents(current).iter(&graph.graph) {
+            if !valid.contains(&parent) && is_skipped(parent) && visited.insert(parent) {
+                skipped.push(parent);
+                queue.push_back(parent);
+            }
+        }
+    }
+
+    skipped
+}
+
+/// All ancestors of a valid node are valid as well.
+pub fn valid_closure<E>(graph: &Adag<E>, valid_nodes: &HashSet<NodeIndex>) -> HashSet<NodeIndex> {
+    let mut valid = valid_nodes.clone();
+    let mut queue = Vec::from_iter(valid_nodes.iter().cloned());
+    while let Some(current) = queue.pop() {
+        for (_, parent) in graph.graph.parents(current).iter(&graph.graph) {
+            if valid.insert(parent) {
+                queue.push(parent);
+            }
+        }
+    }
+    valid
+}
+
 pub fn length_of_path<S: Eq>(path: &VecDeque<S>, left: &S, right: &S) -> Result<usize, ()> {
     let mut left_index = None;
     let mut right_index = None;
@@ -68,16 +219,11 @@ pub fn length_of_path<S: Eq>(path: &VecDeque<S>, left: &S, right: &S) -> Result<
     }
 }
 
-pub fn prune_downwards(
-    graph: &Dag<String, ()>,
-    sources: &[NodeIndex],
-) -> (Dag<String, ()>, HashMap<String, NodeIndex>) {
+pub fn prune_downwards(graph: &Dag<Oid, ()>, sources: &[NodeIndex]) -> Dag<Oid, ()> {
     let mut q: Vec<NodeIndex> = sources.to_owned();
     let mut marked: HashSet<NodeIndex> = HashSet::from_iter(sources.iter().cloned());
 
-    while !q.is_empty() {
-        let current = q.pop().unwrap();
-
+    while let Some(current) = q.pop() {
         for (_, child) in graph.children(current).iter(graph) {
             if marked.insert(child) {
                 q.push(child);
@@ -85,21 +231,48 @@ pub fn prune_downwards(
         }
     }
 
-    let new_graph = graph.filter_map(
-        |n, s| {
+    graph.filter_map(
+        |n, oid| {
             if marked.contains(&n) {
-                Some(s.clone())
+                Some(*oid)
             } else {
                 None
             }
         },
         |_, _| Some(()),
-    );
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut indexation = HashMap::<String, NodeIndex>::new();
-    for index in new_graph.node_identifiers() {
-        indexation.insert(new_graph.node_weight(index).unwrap().clone(), index);
+    const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    #[test]
+    fn parses_full_hashes() {
+        let oid = HASH.parse::<Oid>().unwrap();
+        assert_eq!(oid.to_string(), HASH);
+        assert_eq!(HASH.to_uppercase().parse::<Oid>(), Ok(oid));
     }
 
-    (new_graph, indexation)
+    #[test]
+    fn rejects_other_hashes() {
+        for hash in [
+            "",
+            &HASH[..7],
+            &HASH[..39],
+            &format!("{}0", HASH),
+            &HASH.replace('a', "g"),
+            &HASH.replace("01", "+1"),
+            &HASH.replace("ab", "\u{e9}"),
+            //SHA-256
+            &HASH.repeat(2)[..64],
+        ] {
+            assert_eq!(
+                hash.parse::<Oid>(),
+                Err(format!("{} is not a SHA-1 hash", hash))
+            );
+        }
+    }
 }
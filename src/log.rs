@@ -1,17 +1,131 @@
 use std::{
-    fs::{self, OpenOptions, create_dir_all},
+    fs::{self, OpenOptions},
+    path::{Path, PathBuf},
     time::Duration,
 };
 
-use chrono::Utc;
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
 use std::io::Write;
 
 use crate::{
     process::ProcessResponse,
-    regression::{self, RegressionPoint},
+    regression::{Interval, Probability, RegressionPoint, TestResult},
+    terms::Terms,
     Args,
 };
 
+/// Everything that happens during a search. Each event is written as one JSON
+/// object per line to the event log, tagged with its name and a timestamp.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    SearchStarted {
+        arguments: &'a Args,
+        working_directory: Option<PathBuf>,
+        scheduling: &'a str,
+        sources: &'a [String],
+        targets: &'a [String],
+    },
+    PathPicked {
+        search: usize,
+        source: String,
+        target: String,
+        length: usize,
+        path: Vec<String>,
+    },
+    /// `step` is either "path" for a new path of the extended search,
+    /// "parents" for the initial parents that are checked or "parent" for a
+    /// parent that is added later.
+    ExtendedSearchStep {
+        search: usize,
+        step: &'static str,
+        commits: Vec<String>,
+    },
+    /// State of the speculation tree of bisect after an action ("init",
+    /// "result" or "job").
+    BisectStep {
+        action: &'static str,
+        commit: Option<String>,
+        in_progress: Vec<String>,
+        interrupting: Vec<String>,
+        tree: String,
+    },
+    /// Most likely regression points of the bayesian search, initially and
+    /// after each result.
+    PosteriorUpdated {
+        commit: Option<String>,
+        most_likely: Vec<Probability>,
+    },
+    JobDispatched {
+        pid: u32,
+        commit: &'a str,
+        tree: Option<&'a str>,
+    },
+    JobFinished {
+        pid: u32,
+        commit: &'a str,
+        result: &'a TestResult,
+        /// Name of the result in the terms of the search.
+        term: &'a str,
+        all: f64,
+        setup: f64,
+        query: f64,
+        #[serde(skip_serializing_if = "<[f64]>::is_empty")]
+        metrics: &'a [f64],
+    },
+    JobFailed {
+        pid: u32,
+        commit: &'a str,
+        error: String,
+    },
+    /// Samples of the sources, the reference for relative changes of the
+    /// metric.
+    BaselineMeasured {
+        commits: &'a [String],
+        mean: f64,
+        metrics: &'a [f64],
+    },
+    CachedResult {
+        commit: &'a str,
+        result: &'a TestResult,
+        term: &'a str,
+    },
+    /// Result of a commit that was known before the search.
+    ImportedResult {
+        commit: &'a str,
+        result: &'a TestResult,
+        term: &'a str,
+    },
+    InterruptIssued {
+        pid: u32,
+        commit: &'a str,
+    },
+    RegressionFound(&'a RegressionPoint),
+    SearchFinished {
+        duration: f64,
+        cancelled: bool,
+        queries: u32,
+        total: Times,
+        average: Times,
+        intervals: &'a [Interval],
+    },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    time: String,
+    #[serde(flatten)]
+    event: Event<'a>,
+}
+
+#[derive(Serialize)]
+pub struct Times {
+    all: f64,
+    setup: f64,
+    query: f64,
+}
+
 pub struct TemporalLogData {
     all_sum: f64,
     setup_sum: f64,
@@ -19,49 +133,49 @@ pub struct TemporalLogData {
     len: u32,
 }
 
-pub fn write_header(directory: &std::path::Path, args: &Args, sources: &Vec<String>, targets: &Vec<String>) -> std::path::PathBuf {
+/// Creates a new directory for this search inside of `directory` and starts
+/// the event log.
+pub fn write_header(
+    directory: &Path,
+    args: &Args,
+    sources: &[String],
+    targets: &[String],
+) -> PathBuf {
     let date = Utc::now();
     let directory_name = date.format("%Y%m%d_%H%M%S").to_string();
 
-    let header = format!(
-        "date: {}
-repository: {}
-test: {}
-worktree location: {:?}
-processes: {},
-no propagate: {},
-interrupt: {},
-no extended: {}
-search mode: {},
-scheduling: {}, 
-start: {:?},
-targets: {:?},
-",
-        date.format("%Y-%m-%d %H:%M:%S"),
-        args.repository.display(),
-        args.test.display(),
-        args.worktree_location,
-        args.processes,
-        args.no_propagate,
-        args.interrupt,
-        args.no_extended,
-        args.search_mode,
-        regression::NAME,
-        sources,
-        targets
-    );
-
-    let header2 = "pid,commit,status,all,setup,query\n";
-
     let inner_path = directory.join(directory_name);
     fs::create_dir_all(&inner_path).expect("Couldn't create log directory!");
     fs::create_dir_all(output_path(&inner_path)).expect("Couldn't create log directory!");
+    fs::File::create(event_path(&inner_path)).expect("Couldn't create event log!");
 
-    fs::write(summary_path(&inner_path), header).expect("Couldn't create benchmark file!");
-    fs::write(query_path(&inner_path), header2).expect("Couldn't create benchmark file!");
+    write_event(
+        &inner_path,
+        Event::SearchStarted {
+            arguments: args,
+            working_directory: std::env::current_dir().ok(),
+            scheduling: &args.scheduling,
+            sources,
+            targets,
+        },
+    );
     inner_path
 }
 
+pub fn write_event(path: &Path, event: Event) {
+    let record = Record {
+        time: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        event,
+    };
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(event_path(path))
+        .expect("Couldn't open event log!");
+
+    let line = serde_json::to_string(&record).expect("Couldn't serialize event!");
+    writeln!(&mut file, "{}", line).expect("Couldn't write to event log!");
+}
+
 pub fn empty() -> TemporalLogData {
     TemporalLogData {
         all_sum: 0.0,
@@ -73,14 +187,10 @@ pub fn empty() -> TemporalLogData {
 
 pub fn add_result(
     result: &ProcessResponse,
-    path: &std::path::Path,
+    path: &Path,
     log_data: &mut TemporalLogData,
+    terms: &Terms,
 ) {
-    let mut file = OpenOptions::new()
-        .append(true)
-        .open(query_path(path))
-        .unwrap();
-
     match &result.result {
         Ok((res, exe_data)) => {
             log_data.all_sum += exe_data.all.as_secs_f64();
@@ -88,95 +198,64 @@ pub fn add_result(
             log_data.query_sum += exe_data.query.as_secs_f64();
             log_data.len += 1;
 
-            writeln!(
-                &mut file,
-                "{},{},{},{},{},{}",
-                result.pid,
-                result.commit,
-                res,
-                exe_data.all.as_secs_f64(),
-                exe_data.setup.as_secs_f64(),
-                exe_data.query.as_secs_f64(),
-            )
-            .unwrap();
-        }
-        Err(err) => {
-            writeln!(&mut file, "{},{},{}", result.pid, result.commit, err).unwrap();
+            write_event(
+                path,
+                Event::JobFinished {
+                    pid: result.pid,
+                    commit: &result.commit,
+                    result: res,
+                    term: terms.name(res),
+                    all: exe_data.all.as_secs_f64(),
+                    setup: exe_data.setup.as_secs_f64(),
+                    query: exe_data.query.as_secs_f64(),
+                    metrics: &exe_data.metrics,
+                },
+            );
         }
+        Err(err) => write_event(
+            path,
+            Event::JobFailed {
+                pid: result.pid,
+                commit: &result.commit,
+                error: err.to_string(),
+            },
+        ),
     };
 }
 
 pub fn write_summary(
     overall_duration: &Duration,
-    regression_points: &Vec<RegressionPoint>,
-    path: &std::path::Path,
-    log_data: &mut TemporalLogData,
+    cancelled: bool,
+    intervals: &[Interval],
+    path: &Path,
+    log_data: &TemporalLogData,
 ) {
-    let mut file = OpenOptions::new()
-        .append(true)
-        .open(query_path(path))
-        .unwrap();
-
-    writeln!(&mut file, "---").unwrap();
-
-    writeln!(&mut file, "regression point,target").unwrap();
-    for reg in regression_points {
-        writeln!(&mut file, "{},{}", reg.regression_point, reg.target).unwrap();
-    }
-
-    writeln!(&mut file, "---").unwrap();
-    writeln!(
-        &mut file,
-        "-,-,-,{},{},{}",
-        log_data.all_sum, log_data.setup_sum, log_data.query_sum
-    )
-    .unwrap();
-    writeln!(
-        &mut file,
-        "-,-,-,{},{},{}",
-        log_data.all_sum / log_data.len as f64,
-        log_data.setup_sum / log_data.len as f64,
-        log_data.query_sum / log_data.len as f64,
-    )
-    .unwrap();
-
-    writeln!(
-        &mut file,
-        "overall execution time: {}",
-        overall_duration.as_secs_f64()
-    )
-    .unwrap();
-}
-
-fn summary_path(path: &std::path::Path) -> std::path::PathBuf {
-    path.join("arguments")
+    let len = log_data.len as f64;
+    write_event(
+        path,
+        Event::SearchFinished {
+            duration: overall_duration.as_secs_f64(),
+            cancelled,
+            queries: log_data.len,
+            total: Times {
+                all: log_data.all_sum,
+                setup: log_data.setup_sum,
+                query: log_data.query_sum,
+            },
+            average: Times {
+                all: log_data.all_sum / len,
+                setup: log_data.setup_sum / len,
+                query: log_data.query_sum / len,
+            },
+            intervals,
+        },
+    );
 }
 
-fn query_path(path: &std::path::Path) -> std::path::PathBuf {
-    path.join("queries")
+pub fn event_path(path: &Path) -> PathBuf {
+    path.join("events.jsonl")
 }
 
-pub fn output_path(path: &std::path::Path) -> std::path::PathBuf {
+pub fn output_path(path: &Path) -> PathBuf {
     path.join("output")
 }
-
-pub fn add_dir(name: &str, path: &std::path::Path) -> std::path::PathBuf {
-    let new_path = path.join(name);
-    create_dir_all(&new_path).expect("couldn't create log directory");
-    new_path
-}
-
-pub fn create_file(name: &str, path: &std::path::Path) -> std::path::PathBuf {
-    let new_path = path.join(name);
-    fs::File::create(&new_path).unwrap_or_else(|_| panic!("Creating {:?} failed", &new_path));
-    new_path
-}
-
-pub fn write_to_file(text: &str, path: &std::path::PathBuf) {
-    let mut file = OpenOptions::new()
-    .append(true)
-    .open(path)
-    .unwrap();
-    
-    file.write_all(text.as_bytes()).unwrap_or_else(|_| panic!("Couldn't write to {:?}", path));
-}
\ No newline at end of file
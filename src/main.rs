@@ -1,45 +1,189 @@
+mod bisect_log;
+mod cache;
+mod clean;
+mod config;
+mod daemon;
 mod dvcs;
 mod graph;
 mod log;
 mod manage;
+mod manual;
+mod metric;
+mod output;
 mod process;
 mod regression;
+mod report;
+mod terms;
+mod verdict;
 
-use dvcs::{git::Git, DVCS};
+use dvcs::{git::Git, gitoxide::Gitoxide, DVCS};
 use manage::Options;
+use process::Hooks;
 use regression::{
+    bayesian_search::{BayesianSearch, BayesianSettings},
     binary_search::BinarySearch,
+    galloping_search::{GallopingSearch, SourceGallopingSearch},
+    interval_search::Scheduling,
     linear_search::LinearSearch,
     multiplying_search::MultiplyingSearch,
-    path_selection::{longest_path::LongestPath, shortest_path::ShortestPath},
+    path_selection::{
+        longest_path::LongestPath, max_coverage::MaxCoverage, shortest_path::ShortestPath,
+        PathSelection,
+    },
     rpa_search::RPA,
     rpa_util::Settings,
 };
 
 use crate::{
     manage::start,
-    regression::{git_bisect::GitBisect, RegressionAlgorithm},
+    regression::{git_bisect::GitBisect, PathAlgorithm, RegressionAlgorithm, TestResult},
 };
-use clap::Parser;
+use clap::{ArgMatches, CommandFactory, ErrorKind, FromArgMatches, Parser, Subcommand};
+use daggy::Walker;
+use serde::Serialize;
+use std::collections::HashSet;
 
-#[derive(Parser, Debug)]
-#[clap(version, about, long_about = None)]
+#[derive(Parser, Debug, Serialize)]
+#[clap(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Args {
-    pub repository: std::path::PathBuf,
-    pub test: std::path::PathBuf,
+    #[clap(subcommand)]
+    #[serde(skip)]
+    pub command: Option<Command>,
+
+    /// Defaults to the current directory
+    pub repository: Option<std::path::PathBuf>,
+    /// Path to a test script or name of a test in the configuration
+    pub test: Option<std::path::PathBuf>,
+    /// Arguments of a test command of the configuration
+    #[clap(skip)]
+    pub test_arguments: Vec<String>,
+
+    /// Lines of output of the regression point and its parents that are shown
+    #[clap(long, value_parser, value_name = "LINES", default_value_t = 20)]
+    pub output_tail: usize,
+
+    /// Shows a diff of the output of the regression point and its parents
+    #[clap(long, action, overrides_with = "no-diff-output")]
+    pub diff_output: bool,
+
+    /// Overrides "diff-output = true" of the configuration
+    #[clap(long, action, overrides_with = "diff-output")]
+    #[serde(skip)]
+    pub no_diff_output: bool,
+
+    /// Maps the outcome of the test to a result, e.g. "stdout:FAIL: test_foo=bad"
+    #[clap(long = "verdict", value_parser, value_name = "RULE")]
+    pub verdicts: Vec<String>,
+
+    /// Classifies commits by a metric of the test: "stdout" (last line) or "file:<PATH>"
+    #[clap(long, value_parser, value_name = "SOURCE")]
+    pub metric: Option<String>,
+
+    /// Commits with a larger metric are bad
+    #[clap(long, value_parser, value_name = "VALUE")]
+    pub threshold: Option<f64>,
+
+    /// Commits whose metric is worse than the sources by more percent are bad
+    #[clap(long, value_parser, value_name = "PERCENT")]
+    pub max_change: Option<f64>,
+
+    /// Larger metrics are better
+    #[clap(long, action, overrides_with = "no-higher-is-better")]
+    pub higher_is_better: bool,
+
+    /// Overrides "higher-is-better = true" of the configuration
+    #[clap(long, action, overrides_with = "higher-is-better")]
+    #[serde(skip)]
+    pub no_higher_is_better: bool,
+
+    /// Number of times the test is run per commit
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..), value_name = "AMOUNT", default_value_t = 1)]
+    pub samples: u32,
+
+    /// Significance level of the test, whether a change is real
+    #[clap(long, value_parser, value_name = "ALPHA", default_value_t = 0.05)]
+    pub significance: f64,
+
+    /// Probability that a good commit is tested bad, for the search mode bayes
+    #[clap(long, value_parser, value_name = "RATE", default_value_t = 0.1)]
+    pub false_positive_rate: f64,
+
+    /// Probability that a bad commit is tested good, for the search mode bayes
+    #[clap(long, value_parser, value_name = "RATE", default_value_t = 0.1)]
+    pub false_negative_rate: f64,
+
+    /// Probability of the regression point at which the search mode bayes stops
+    #[clap(long, value_parser, value_name = "PROBABILITY", default_value_t = 0.95)]
+    pub confidence: f64,
+
+    /// Searches the commit that fixed the test: it fails on the sources and passes on the targets
+    #[clap(long, action, overrides_with = "no-find-fix")]
+    pub find_fix: bool,
+
+    /// Overrides "find-fix = true" of the configuration
+    #[clap(long, action, overrides_with = "find-fix")]
+    #[serde(skip)]
+    pub no_find_fix: bool,
+
+    /// Name of the state of the sources, "good" or "broken" with --find-fix
+    #[clap(long, value_parser, value_name = "TERM")]
+    pub term_old: Option<String>,
+
+    /// Name of the state of the targets, "bad" or "fixed" with --find-fix
+    #[clap(long, value_parser, value_name = "TERM")]
+    pub term_new: Option<String>,
+
+    /// Uses the results of a `git bisect log`
+    #[clap(parse(from_os_str), long, value_name = "FILE")]
+    pub import_bisect_log: Option<std::path::PathBuf>,
+
+    /// Commits that are tested first, together with their parents
+    #[clap(long, value_parser, value_name = "COMMITS")]
+    pub suspect: Option<String>,
+
+    /// Commits that are known to be good (in the state of the sources)
+    #[clap(long, value_parser, value_name = "COMMITS")]
+    pub known_good: Option<String>,
+
+    /// Commits that are known to be bad (in the state of the targets)
+    #[clap(long, value_parser, value_name = "COMMITS")]
+    pub known_bad: Option<String>,
+
+    /// Writes all results as log for `git bisect replay`
+    #[clap(parse(from_os_str), long, value_name = "FILE")]
+    pub export_bisect_log: Option<std::path::PathBuf>,
+
+    /// The user marks each checked out commit instead of running a test
+    #[clap(long, action)]
+    pub manual: bool,
+
+    /// Profile of the configuration file
+    #[clap(long, value_parser, value_name = "NAME")]
+    pub profile: Option<String>,
 
     #[clap(short, long, value_parser, value_name = "AMOUNT", default_value_t = 1)]
     pub processes: u32,
 
-    #[clap(short, long, value_parser)]
-    pub source: String,
+    #[clap(short, long, value_parser, required = true)]
+    pub source: Option<String>,
 
-    #[clap(short, long, value_parser)]
-    pub target: String,
+    #[clap(short, long, value_parser, required = true)]
+    pub target: Option<String>,
 
-    #[clap(long, action)]
+    #[clap(long, action, overrides_with = "propagate")]
     pub no_propagate: bool,
 
+    /// Overrides "no-propagate = true" of the configuration
+    #[clap(long, action, overrides_with = "no-propagate")]
+    #[serde(skip)]
+    pub propagate: bool,
+
     #[clap(parse(from_os_str), long)]
     pub worktree_location: Option<std::path::PathBuf>,
 
@@ -51,94 +195,603 @@ pub struct Args {
     )]
     pub search_mode: String,
 
+    /// Reads commits with "git" or directly from the repository with "gitoxide"
+    #[clap(
+        long,
+        value_parser = ["git", "gitoxide"],
+        value_name = "BACKEND",
+        default_value = "git"
+    )]
+    pub backend: String,
+
+    /// How the interval searches of the exrpa modes schedule their samples on the processes
+    #[clap(
+        long,
+        value_parser = Scheduling::NAMES,
+        value_name = "SCHEDULING",
+        default_value = "eager"
+    )]
+    pub scheduling: String,
+
     #[clap(parse(from_os_str), short, long, value_name = "DIRECTORY")]
     pub log: Option<std::path::PathBuf>,
 
-    #[clap(long, action)]
+    #[clap(long, action, overrides_with = "no-interrupt")]
     pub interrupt: bool,
 
-    #[clap(long, action)]
+    /// Overrides "interrupt = true" of the configuration
+    #[clap(long, action, overrides_with = "interrupt")]
+    #[serde(skip)]
+    pub no_interrupt: bool,
+
+    #[clap(long, action, overrides_with = "extended")]
     pub no_extended: bool,
+
+    /// Overrides "no-extended = true" of the configuration
+    #[clap(long, action, overrides_with = "no-extended")]
+    #[serde(skip)]
+    pub extended: bool,
+
+    #[clap(parse(from_os_str), long, value_name = "SCRIPT")]
+    pub build: Option<std::path::PathBuf>,
+
+    #[clap(parse(from_os_str), long, value_name = "DIRECTORY")]
+    pub build_location: Option<std::path::PathBuf>,
+
+    #[clap(long, action, overrides_with = "cache")]
+    pub no_cache: bool,
+
+    /// Overrides "no-cache = true" of the configuration
+    #[clap(long, action, overrides_with = "no-cache")]
+    #[serde(skip)]
+    pub cache: bool,
+
+    #[clap(parse(from_os_str), long, value_name = "SCRIPT")]
+    pub worktree_setup: Option<std::path::PathBuf>,
+
+    #[clap(parse(from_os_str), long, value_name = "SCRIPT")]
+    pub pre_query: Option<std::path::PathBuf>,
+
+    #[clap(parse(from_os_str), long, value_name = "SCRIPT")]
+    pub post_query: Option<std::path::PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Removes all worktrees that were created by crs
+    Clean { repository: std::path::PathBuf },
+    /// Renders the log of a search as HTML page
+    Report {
+        /// Log directory of a search or the directory passed to --log
+        log: std::path::PathBuf,
+        /// Defaults to report.html in the log directory of the search
+        #[clap(parse(from_os_str), short, long, value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Runs searches in the background, controlled via a Unix socket
+    Daemon {
+        /// Defaults to crs_daemon.sock in the temporary directory
+        #[clap(parse(from_os_str), long, value_name = "FILE")]
+        socket: Option<std::path::PathBuf>,
+    },
+    /// Marks a commit of a running search with --manual
+    Mark {
+        commit: String,
+        /// good, bad, skip or one of the terms of the search
+        verdict: String,
+        #[clap(parse(from_os_str), long, default_value = ".")]
+        repository: std::path::PathBuf,
+    },
 }
 
 fn main() {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    if let Some(command) = &args.command {
+        match command {
+            Command::Clean { repository } => {
+                clean::clean::<Git>(&repository.display().to_string());
+            }
+            Command::Report { log, output } => {
+                report::report(log, output.as_deref());
+            }
+            Command::Daemon { socket } => {
+                daemon::run(&socket.clone().unwrap_or_else(daemon::default_socket));
+            }
+            Command::Mark {
+                commit,
+                verdict,
+                repository,
+            } => match manual::send(&manual::socket_path(repository), commit, verdict) {
+                Ok(reply) => println!("{}", reply),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            },
+        }
+        return;
+    }
 
-    let sources: Vec<String> = args.source.split(',').map(|s| s.to_string()).collect();
-    let targets: Vec<String> = args.target.split(',').map(|s| s.to_string()).collect();
+    let search = arguments(&matches)
+        .and_then(prepare)
+        .unwrap_or_else(|e| e.exit());
+    let control = manage::Control::default();
+    manage::cancel_on_signal(&control);
+    match search.run(&control) {
+        Ok(true) => std::process::exit(130),
+        Ok(false) => {}
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Everything that is needed to run a search. Preparing a search has no side
+/// effects, the log, the exported bisect log and the socket of a manual search
+/// are only created when it runs.
+pub struct Search {
+    core: LoadCore,
+    args: Args,
+    targets: Vec<String>,
+    pub repository: String,
+    backend: String,
+    test: Vec<String>,
+    processes: u32,
+    options: Options,
+    /// All commits between the sources and targets.
+    pub commits: Vec<String>,
+}
+
+impl Search {
+    pub fn terms(&self) -> &terms::Terms {
+        &self.options.terms
+    }
+
+    /// Runs the search, returns whether it was cancelled.
+    pub fn run(mut self, control: &manage::Control) -> Result<bool, String> {
+        let sources = &self.options.sources;
+        let log_location = self
+            .args
+            .log
+            .as_ref()
+            .map(|b_dir| log::write_header(b_dir, &self.args, sources, &self.targets));
+        self.options.log_location = log_location.clone();
+
+        if let Some(path) = &self.options.bisect_log {
+            let terms = &self.options.terms;
+            bisect_log::create(path, terms);
+            //`git bisect replay` only keeps the last bad commit.
+            bisect_log::append(path, &self.targets[0], &TestResult::False, terms);
+            for source in sources {
+                bisect_log::append(path, source, &TestResult::True, terms);
+            }
+        }
 
-    let log_location = args
-        .log
+        let socket = if self.args.manual {
+            let socket = manual::socket_path(std::path::Path::new(&self.repository));
+            let marks = manual::Marks::new(self.options.terms.clone());
+            manual::listen(&marks, &socket)?;
+            self.options.manual = Some(marks);
+            Some(socket)
+        } else {
+            None
+        };
+
+        eprintln!("Preparing core ...");
+        let mut core = (self.core)(log_location);
+        eprintln!("Starting search ...");
+        let start = match self.backend.as_str() {
+            "gitoxide" => start::<Gitoxide>,
+            _ => start::<Git>,
+        };
+        let cancelled = start(
+            core.as_mut(),
+            &self.repository,
+            self.processes,
+            &self.test,
+            self.options,
+            control,
+        );
+        if let Some(socket) = socket {
+            let _ = std::fs::remove_file(socket);
+        }
+        Ok(cancelled)
+    }
+}
+
+/// Reads the arguments of the command line, or of a search that is started by
+/// the daemon, together with the configuration of the repository.
+pub fn arguments(matches: &ArgMatches) -> Result<Args, clap::Error> {
+    let mut args = Args::from_arg_matches(matches)?;
+    config::apply(&mut args, matches).map_err(|e| clap::Error::raw(ErrorKind::Io, e))?;
+    if args.test.is_none() && !args.manual {
+        return Err(Args::command().error(
+            ErrorKind::MissingRequiredArgument,
+            "No test given. Pass it as argument or set `test` in crs.toml.",
+        ));
+    }
+    Ok(args)
+}
+
+/// Prepares a search from its arguments.
+pub fn prepare(args: Args) -> Result<Search, clap::Error> {
+    //Without a subcommand, clap ensures that source and target are present.
+    //The repository and test are set by the configuration, only a manual
+    //search has no test.
+    let repository = args.repository.clone().unwrap();
+    let test = args.test.clone().unwrap_or_default();
+    let sources: Vec<String> = args
+        .source
         .as_ref()
-        .map(|b_dir| log::write_header(b_dir, &args, &sources, &targets));
+        .unwrap()
+        .split(',')
+        .map(|s| s.to_string())
+        .collect();
+    let targets: Vec<String> = args
+        .target
+        .as_ref()
+        .unwrap()
+        .split(',')
+        .map(|s| s.to_string())
+        .collect();
 
     let worktree_location = args
         .worktree_location
         .as_ref()
         .map(|path| path.display().to_string());
 
-    let options = Options {
+    let hooks = Hooks {
+        worktree_setup: args
+            .worktree_setup
+            .as_ref()
+            .map(|path| path.display().to_string()),
+        pre_query: args
+            .pre_query
+            .as_ref()
+            .map(|path| path.display().to_string()),
+        post_query: args
+            .post_query
+            .as_ref()
+            .map(|path| path.display().to_string()),
+    };
+
+    let rules = args
+        .verdicts
+        .iter()
+        .map(|rule| rule.parse())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| {
+            Args::command().error(ErrorKind::InvalidValue, format!("Invalid verdict: {}", err))
+        })?;
+
+    let metric = load_metric(&args, &sources)?;
+    let terms = terms::Terms::new(args.find_fix, args.term_old.clone(), args.term_new.clone());
+
+    let imported_results = match &args.import_bisect_log {
+        Some(path) => bisect_log::read(path, &terms).map_err(|err| {
+            Args::command().error(
+                ErrorKind::InvalidValue,
+                format!("Invalid bisect log: {}", err),
+            )
+        })?,
+        None => vec![],
+    };
+
+    let mut options = Options {
         worktree_location,
-        log_location: log_location.clone(),
+        log_location: None,
         do_interrupt: args.interrupt,
+        hooks,
+        verdicts: verdict::Verdicts::new(rules),
+        build_script: args.build.as_ref().map(|path| path.display().to_string()),
+        build_location: args
+            .build_location
+            .clone()
+            .unwrap_or_else(|| repository.join(".crs").join("build")),
+        use_cache: !args.no_cache,
+        output_tail: args.output_tail,
+        diff_output: args.diff_output,
+        metric,
+        sources: sources.clone(),
+        terms,
+        imported_results,
+        bisect_log: args.export_bisect_log.clone(),
+        manual: None,
+        suspects: vec![],
     };
 
-    let repo_path = &args.repository.display().to_string();
-    let test_path = &args.test.display().to_string();
+    let repo_path = repository.display().to_string();
 
     eprintln!("Processing commit graph ...");
-    let g = Git::commit_graph(repo_path, sources, targets).unwrap();
-    eprintln!("Preparing core ...");
-    let mut rpa = load_core(&args, g, log_location);
-    eprintln!("Starting search ...");
+    let commit_graph = match args.backend.as_str() {
+        "gitoxide" => Gitoxide::commit_graph,
+        _ => Git::commit_graph,
+    };
+    let g = commit_graph(&repo_path, sources, targets.clone()).map_err(|_| {
+        clap::Error::raw(
+            ErrorKind::Io,
+            format!("Couldn't read the commit graph of {}", repo_path),
+        )
+    })?;
+    let commits = g.hashes().collect::<Vec<_>>();
+    options.imported_results = resolve_imported(options.imported_results, &commits);
+
+    let rev_list = match args.backend.as_str() {
+        "gitoxide" => Gitoxide::rev_list,
+        _ => Git::rev_list,
+    };
+    let revisions = |list: &Option<String>, option: &str| {
+        resolve_revisions(list, option, &g, &repo_path, rev_list)
+    };
+    for (list, option, result) in [
+        (&args.known_good, "known-good", TestResult::True),
+        (&args.known_bad, "known-bad", TestResult::False),
+    ] {
+        let hashes = revisions(list, option)?;
+        options
+            .imported_results
+            .extend(hashes.into_iter().map(|hash| (hash, result.clone())));
+    }
+    options.suspects = resolve_suspects(&revisions(&args.suspect, "suspect")?, &g);
+    let core = load_core(&args, g)?;
+
+    Ok(Search {
+        core,
+        repository: repo_path,
+        backend: args.backend.clone(),
+        test: std::iter::once(test.display().to_string())
+            .chain(args.test_arguments.iter().cloned())
+            .collect(),
+        processes: args.processes,
+        options,
+        commits,
+        targets,
+        args,
+    })
+}
+
+type RevList = fn(&str, &str) -> Result<Vec<String>, String>;
+
+//Resolves the revisions "<rev1>,<rev2>" of an option with the backend, and
+//ranges "<from>..<to>" to their commits. Commits outside of the search don't
+//affect it and are dropped, but not without notice: a known good commit is
+//often an ancestor of a source, which is good already.
+fn resolve_revisions(
+    list: &Option<String>,
+    option: &str,
+    graph: &graph::Adag<()>,
+    repository: &str,
+    rev_list: RevList,
+) -> Result<Vec<String>, clap::Error> {
+    let mut resolved = vec![];
+    let mut seen = HashSet::new();
+    for revision in commit_list(list) {
+        let hashes = rev_list(repository, &revision).map_err(|err| {
+            Args::command().error(
+                ErrorKind::InvalidValue,
+                format!("Invalid revision for --{}: {}", option, err),
+            )
+        })?;
+        let (inside, outside): (Vec<_>, Vec<_>) = hashes
+            .into_iter()
+            .partition(|hash| graph.get_index(hash).is_some());
+
+        if revision.contains("..") {
+            if !outside.is_empty() {
+                eprintln!(
+                    "--{} {}: {} of {} commits are not part of the search, ignored",
+                    option,
+                    revision,
+                    outside.len(),
+                    outside.len() + inside.len()
+                );
+            }
+        } else if let Some(hash) = outside.first() {
+            let ancestor = graph.sources.iter().any(|source| {
+                rev_list(repository, &format!("{}..{}", source, hash))
+                    .is_ok_and(|commits| commits.is_empty())
+            });
+            let reason = if ancestor {
+                "is an ancestor of a source"
+            } else {
+                "is not part of the search"
+            };
+            eprintln!("--{} {} {}, ignored", option, revision, reason);
+        }
+
+        for hash in inside {
+            if seen.insert(hash.clone()) {
+                resolved.push(hash);
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+//Matches the commits of a bisect log with the commit graph. Abbreviated hashes
+//are fine, as long as they are unique. Commits outside of the graph don't
+//affect the search and are dropped.
+fn resolve_imported(
+    results: Vec<(String, TestResult)>,
+    commits: &[String],
+) -> Vec<(String, TestResult)> {
+    results
+        .into_iter()
+        .filter_map(|(commit, result)| match resolve(&commit, commits) {
+            Ok(hash) => Some((hash, result)),
+            Err(err) => {
+                eprintln!("Imported commit {}, ignored", err);
+                None
+            }
+        })
+        .collect()
+}
+
+//Suspects are followed by their parents, so that a bad suspect with good
+//parents is found without searching.
+fn resolve_suspects(suspects: &[String], graph: &graph::Adag<()>) -> Vec<String> {
+    let mut resolved = vec![];
+    for hash in suspects {
+        let index = graph.index(hash);
+        let parents = graph
+            .graph
+            .parents(index)
+            .iter(&graph.graph)
+            .map(|(_, parent)| graph.hash_from_index(parent));
+        for commit in std::iter::once(hash.clone()).chain(parents) {
+            if !resolved.contains(&commit) {
+                resolved.push(commit);
+            }
+        }
+    }
+    resolved
+}
+
+//Commits given as "<hash1>,<hash2>" on the command line.
+fn commit_list(list: &Option<String>) -> impl Iterator<Item = String> + '_ {
+    list.iter()
+        .flat_map(|list| list.split(','))
+        .filter(|commit| !commit.is_empty())
+        .map(|commit| commit.to_string())
+}
 
-    start::<Git>(rpa.as_mut(), repo_path, args.processes, test_path, options);
+/// Finds the commit of the search with the (abbreviated) hash.
+pub fn resolve(commit: &str, commits: &[String]) -> Result<String, String> {
+    let matches = commits
+        .iter()
+        .filter(|hash| hash.starts_with(commit))
+        .collect::<Vec<_>>();
+    match matches.as_slice() {
+        [hash] => Ok((*hash).clone()),
+        [] => Err(format!("{} is not part of the search", commit)),
+        _ => Err(format!("{} is ambiguous", commit)),
+    }
 }
 
-fn load_core(
-    args: &Args,
-    graph: graph::Adag<String, ()>,
-    log_location: Option<std::path::PathBuf>,
-) -> Box<dyn RegressionAlgorithm> {
+fn load_metric(args: &Args, sources: &[String]) -> Result<Option<metric::Metric>, clap::Error> {
+    let invalid = |message: &str| Err(Args::command().error(ErrorKind::ArgumentConflict, message));
+
+    if args.manual && args.metric.is_some() {
+        return invalid("--metric can't be used with --manual");
+    }
+
+    let source = match &args.metric {
+        Some(source) => match source.parse::<metric::MetricSource>() {
+            Ok(source) => source,
+            Err(err) => return invalid(&err),
+        },
+        None => {
+            if args.threshold.is_some() || args.max_change.is_some() {
+                return invalid("--threshold and --max-change need a --metric");
+            }
+            return Ok(None);
+        }
+    };
+    if args.threshold.is_none() && args.max_change.is_none() {
+        return invalid("--metric needs a --threshold or --max-change");
+    }
+    if args.max_change.is_some() && args.find_fix {
+        return invalid("--max-change can't be used with --find-fix, use --threshold instead");
+    }
+    if args.max_change.is_some() && sources.is_empty() {
+        return invalid("--max-change needs sources to compare with");
+    }
+
+    Ok(Some(metric::Metric {
+        source,
+        samples: args.samples,
+        threshold: args.threshold,
+        max_change: args.max_change,
+        higher_is_better: args.higher_is_better,
+        significance: args.significance,
+        baseline: vec![],
+    }))
+}
+
+fn bayesian_settings(args: &Args) -> Result<BayesianSettings, clap::Error> {
+    let invalid = |message: &str| Args::command().error(ErrorKind::InvalidValue, message);
+    let is_rate = |rate: f64| (0.0..1.0).contains(&rate);
+    if !is_rate(args.false_positive_rate) || !is_rate(args.false_negative_rate) {
+        return Err(invalid("Error rates must be at least 0 and less than 1"));
+    }
+    if args.false_positive_rate + args.false_negative_rate >= 1.0 {
+        return Err(invalid(
+            "The test tells nothing, if the error rates add up to 1 or more",
+        ));
+    }
+    if !(args.confidence > 0.0 && args.confidence < 1.0) {
+        return Err(invalid("The confidence must be between 0 and 1"));
+    }
+
+    Ok(BayesianSettings {
+        false_positive_rate: args.false_positive_rate,
+        false_negative_rate: args.false_negative_rate,
+        confidence: args.confidence,
+    })
+}
+
+//Cores log when they are created, so they are only created once the search
+//runs and the log exists.
+type LoadCore = Box<dyn FnOnce(Option<std::path::PathBuf>) -> Core + Send>;
+type Core = Box<dyn RegressionAlgorithm + Send>;
+
+fn load_core(args: &Args, graph: graph::Adag<()>) -> Result<LoadCore, clap::Error> {
     let settings = Settings {
         propagate: !args.no_propagate,
         extended_search: !args.no_extended,
+        scheduling: args
+            .scheduling
+            .parse()
+            .map_err(|err: String| Args::command().error(ErrorKind::InvalidValue, err))?,
     };
 
-    match args.search_mode.as_str() {
-        "exrpa-long-bin" => Box::new(RPA::<LongestPath, BinarySearch, ()>::new(
-            graph,
-            settings,
-            log_location,
-        )),
-        "exrpa-long-lin" => Box::new(RPA::<LongestPath, LinearSearch, ()>::new(
-            graph,
-            settings,
-            log_location,
-        )),
-        "exrpa-long-mul" => Box::new(RPA::<LongestPath, MultiplyingSearch, ()>::new(
-            graph,
-            settings,
-            log_location,
-        )),
-        "exrpa-short-bin" => Box::new(RPA::<ShortestPath, BinarySearch, ()>::new(
-            graph,
-            settings,
-            log_location,
-        )),
-        "exrpa-short-lin" => Box::new(RPA::<ShortestPath, LinearSearch, ()>::new(
-            graph,
-            settings,
-            log_location,
-        )),
-        "exrpa-short-mul" => Box::new(RPA::<ShortestPath, MultiplyingSearch, ()>::new(
-            graph,
-            settings,
-            log_location,
-        )),
-        "bisect" => Box::new(GitBisect::new(graph, log_location)),
+    let core: LoadCore = match args.search_mode.as_str() {
+        "exrpa-long-bin" => rpa::<LongestPath, BinarySearch>(graph, settings),
+        "exrpa-long-lin" => rpa::<LongestPath, LinearSearch>(graph, settings),
+        "exrpa-long-mul" => rpa::<LongestPath, MultiplyingSearch>(graph, settings),
+        "exrpa-long-gal" => rpa::<LongestPath, GallopingSearch>(graph, settings),
+        "exrpa-long-galsrc" => rpa::<LongestPath, SourceGallopingSearch>(graph, settings),
+        "exrpa-short-bin" => rpa::<ShortestPath, BinarySearch>(graph, settings),
+        "exrpa-short-lin" => rpa::<ShortestPath, LinearSearch>(graph, settings),
+        "exrpa-short-mul" => rpa::<ShortestPath, MultiplyingSearch>(graph, settings),
+        "exrpa-short-gal" => rpa::<ShortestPath, GallopingSearch>(graph, settings),
+        "exrpa-short-galsrc" => rpa::<ShortestPath, SourceGallopingSearch>(graph, settings),
+        "exrpa-cover-bin" => rpa::<MaxCoverage, BinarySearch>(graph, settings),
+        "exrpa-cover-lin" => rpa::<MaxCoverage, LinearSearch>(graph, settings),
+        "exrpa-cover-mul" => rpa::<MaxCoverage, MultiplyingSearch>(graph, settings),
+        "exrpa-cover-gal" => rpa::<MaxCoverage, GallopingSearch>(graph, settings),
+        "exrpa-cover-galsrc" => rpa::<MaxCoverage, SourceGallopingSearch>(graph, settings),
+        "bisect" => Box::new(move |log_location| Box::new(GitBisect::new(graph, log_location))),
+        "bayes" => {
+            let settings = bayesian_settings(args)?;
+            if graph.targets.len() > 1 {
+                return Err(Args::command().error(
+                    ErrorKind::ArgumentConflict,
+                    "The search mode bayes takes only one target, search the others one by one",
+                ));
+            }
+            Box::new(move |log_location| {
+                Box::new(BayesianSearch::new(graph, settings, log_location))
+            })
+        }
         &_ => {
-            panic!("Invalid search mode! Pick (exrpa-long-bin, exrpa-long-lin, exrpa-long-mul, exrpa-short-bin, exrpa-short-lin, exrpa-short-mul)");
+            return Err(Args::command().error(
+                ErrorKind::InvalidValue,
+                "Invalid search mode! Pick (exrpa-long-bin, exrpa-long-lin, exrpa-long-mul, exrpa-long-gal, exrpa-long-galsrc, exrpa-short-bin, exrpa-short-lin, exrpa-short-mul, exrpa-short-gal, exrpa-short-galsrc, exrpa-cover-bin, exrpa-cover-lin, exrpa-cover-mul, exrpa-cover-gal, exrpa-cover-galsrc, bisect, bayes)",
+            ));
         }
-    }
+    };
+    Ok(core)
+}
+
+fn rpa<P, S>(graph: graph::Adag<()>, settings: Settings) -> LoadCore
+where
+    P: PathSelection + 'static,
+    S: PathAlgorithm + RegressionAlgorithm + 'static,
+    RPA<P, S, ()>: Send,
+{
+    Box::new(move |log_location| Box::new(RPA::<P, S, ()>::new(graph, settings, log_location)))
 }
@@ -1,20 +1,115 @@
+use crate::bisect_log;
+use crate::cache::{Lookup, TreeCache};
 use crate::dvcs::DVCS;
-use crate::log::{self, TemporalLogData};
-use crate::process::{LocalProcess, ProcessError, ProcessResponse};
-use crate::regression::RegressionAlgorithm;
-use std::collections::{HashMap, HashSet};
+use crate::log::{self, Event, TemporalLogData};
+use crate::manual::Marks;
+use crate::metric::{self, Metric};
+use crate::output::{self, Origin, OutputDirectory};
+use crate::process::{BuildStep, Hooks, LocalProcess, ProcessError, ProcessResponse};
+use crate::regression::{AlgorithmResponse, RegressionAlgorithm, RegressionPoint, TestResult};
+use crate::terms::Terms;
+use crate::verdict::Verdicts;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::marker::PhantomData;
-use std::sync::mpsc::{self, RecvError, TryRecvError};
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvError, RecvTimeoutError, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+//How often a waiting search checks whether it has been cancelled.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct Options {
     pub worktree_location: Option<String>,
     pub log_location: Option<std::path::PathBuf>,
     pub do_interrupt: bool,
+    pub hooks: Hooks,
+    pub verdicts: Verdicts,
+    pub build_script: Option<String>,
+    pub build_location: std::path::PathBuf,
+    pub use_cache: bool,
+    pub output_tail: usize,
+    pub diff_output: bool,
+    pub metric: Option<Metric>,
+    /// Commits that are measured first, if the metric needs a baseline.
+    pub sources: Vec<String>,
+    pub terms: Terms,
+    /// Results that are known before the search, e.g. from a `git bisect log`.
+    pub imported_results: Vec<(String, TestResult)>,
+    /// Log of all results in the format of `git bisect log`.
+    pub bisect_log: Option<std::path::PathBuf>,
+    /// The user marks the checked out commits instead of a test script.
+    pub manual: Option<Marks>,
+    /// Commits that are tested before the ones of the algorithm.
+    pub suspects: Vec<String>,
+}
+/// Lets other threads watch and steer a running search, e.g. the daemon.
+#[derive(Clone, Default)]
+pub struct Control {
+    cancelled: Arc<AtomicBool>,
+    state: Arc<Mutex<ControlState>>,
+}
+
+#[derive(Default)]
+struct ControlState {
+    processes: Option<u32>,
+    results: Vec<(String, TestResult)>,
+    status: Status,
+}
+
+/// Snapshot of a search, updated after each step.
+#[derive(Clone, Default, Serialize)]
+pub struct Status {
+    pub running: bool,
+    pub cancelled: bool,
+    pub processes: u32,
+    pub tested: u32,
+    /// Running queries and their process.
+    pub active: BTreeMap<String, u32>,
+    pub path: Option<Vec<String>>,
+    /// Known results in the terms of the search.
+    pub results: BTreeMap<String, String>,
+    pub regression_points: Vec<RegressionPoint>,
+}
+
+impl Control {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Changes the number of processes, surplus processes stop after their
+    /// current query.
+    pub fn set_processes(&self, processes: u32) {
+        self.state.lock().unwrap().processes = Some(processes);
+    }
+
+    /// Adds a result from outside, e.g. of a human. A running query of the
+    /// commit is interrupted.
+    pub fn add_result(&self, commit: String, result: TestResult) {
+        self.state.lock().unwrap().results.push((commit, result));
+    }
+
+    pub fn status(&self) -> Status {
+        self.state.lock().unwrap().status.clone()
+    }
+
+    fn has_requests(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.processes.is_some() || !state.results.is_empty()
+    }
 }
+
 struct ProcessPool<T> {
     next_id: u32,
     empty_slots: u32,
+    /// Processes that are removed as soon as they are idle.
+    surplus: u32,
+    processes: u32,
     idle_processes: Vec<LocalProcess<T>>,
     active_processes: HashMap<u32, LocalProcess<T>>,
     commit_to_process: HashMap<String, u32>,
@@ -25,6 +120,10 @@ struct ProcessPool<T> {
 struct Stats {
     number_jobs: u32,
     interrupted_tests: u32,
+    cached_results: u32,
+    results: HashMap<String, TestResult>,
+    /// Results that were not queried for the commit itself.
+    origins: HashMap<String, Origin>,
 }
 
 impl Stats {
@@ -32,24 +131,33 @@ impl Stats {
         Stats {
             number_jobs: 0,
             interrupted_tests: 0,
+            cached_results: 0,
+            results: HashMap::new(),
+            origins: HashMap::new(),
         }
     }
 }
 
+/// Runs the search until all regression points are found or it's cancelled
+/// via `control`. Returns whether the search was cancelled.
 pub fn start<T: DVCS>(
     core: &mut dyn RegressionAlgorithm,
     repository: &str,
     threads: u32,
-    script_path: &str,
-    options: Options,
-) {
+    test: &[String],
+    mut options: Options,
+    control: &Control,
+) -> bool {
     let mut stats = Stats::new();
+    let cancelled = &control.cancelled;
 
     let (send, recv) = mpsc::channel::<ProcessResponse>();
 
     let mut pool = ProcessPool::<T> {
         next_id: 0,
         empty_slots: threads,
+        surplus: 0,
+        processes: threads,
         idle_processes: Vec::new(),
         active_processes: HashMap::new(),
         commit_to_process: HashMap::new(),
@@ -57,51 +165,166 @@ pub fn start<T: DVCS>(
         _marker: PhantomData,
     };
 
+    let mut cache = if options.use_cache && !core.repeats_tests() {
+        Some(TreeCache::new())
+    } else {
+        None
+    };
+
+    let output_directory = OutputDirectory::new(options.log_location.as_deref());
     let mut log_data = log::empty();
     let start_time = Instant::now();
+
+    let mut ready = true;
+    if options
+        .metric
+        .as_ref()
+        .map(|m| m.needs_baseline())
+        .unwrap_or(false)
+    {
+        match measure_baseline(
+            &mut pool,
+            &send,
+            &recv,
+            repository,
+            test,
+            &options,
+            &output_directory,
+            control,
+            &mut log_data,
+        ) {
+            Ok(baseline) => {
+                stats.number_jobs += options.sources.len() as u32;
+                options.metric.as_mut().unwrap().baseline = baseline;
+            }
+            Err(err) => {
+                eprintln!("Couldn't measure the baseline: {}", err);
+                ready = false;
+            }
+        }
+    }
+
+    //A commit that is tested again needs a new result.
+    let imported = if core.repeats_tests() {
+        HashMap::new()
+    } else {
+        options
+            .imported_results
+            .iter()
+            .cloned()
+            .collect::<HashMap<_, _>>()
+    };
+    for (commit, result) in &options.imported_results {
+        add_imported_result(commit, result, core, &mut stats, &options);
+    }
+    let mut suspects = options.suspects.iter().cloned().collect::<VecDeque<_>>();
+
     //We assume that there is at least one process available in the first
     //iteration.
-    while !core.done() {
+    while ready && !core.done() {
+        if cancelled.load(Ordering::SeqCst) {
+            break;
+        }
+        apply_requests(control, core, &mut stats, &mut pool, &options);
+        if core.done() {
+            break;
+        }
+
         let mut wait = false;
         let capacity = pool.idle_processes.len() as u32 + pool.empty_slots;
         let expected_capacity = capacity + pool.interrupted_processes.len() as u32;
-        match core.next_job(capacity, expected_capacity) {
-            crate::regression::AlgorithmResponse::Job(commit) => {
-                let setup_time = Instant::now();
-                let process = load_process(
-                    &mut pool,
-                    repository,
-                    options.worktree_location.clone(),
-                    &commit,
-                );
-                process.run(
-                    commit,
-                    send.clone(),
-                    script_path.to_string(),
-                    setup_time,
-                    options.log_location.as_ref(),
-                );
-                stats.number_jobs += 1;
+        let mut cached = None;
+        let retry = cache.as_mut().and_then(|c| c.next_retry());
+        let response = match retry {
+            //After the number of processes was reduced, all remaining
+            //processes might be busy.
+            _ if capacity == 0 => AlgorithmResponse::WaitForResult,
+            Some(commit) => AlgorithmResponse::Job(commit),
+            None => match next_suspect(&mut suspects, &stats, &pool) {
+                Some(commit) => AlgorithmResponse::Job(commit),
+                //The algorithm starts with the results of all suspects, like
+                //with imported results.
+                None if options
+                    .suspects
+                    .iter()
+                    .any(|commit| pool.commit_to_process.contains_key(commit)) =>
+                {
+                    AlgorithmResponse::WaitForResult
+                }
+                None => core.next_job(capacity, expected_capacity),
+            },
+        };
+        match response {
+            AlgorithmResponse::Job(commit) if imported.contains_key(&commit) => {
+                add_imported_result(&commit, &imported[&commit], core, &mut stats, &options);
             }
-            crate::regression::AlgorithmResponse::WaitForResult => {
+            AlgorithmResponse::Job(commit) => {
+                let tree = match cache.as_mut().map(|c| c.lookup::<T>(repository, &commit)) {
+                    Some(Lookup::Hit(result, tested)) => {
+                        cached = Some((commit.clone(), result, tested));
+                        None
+                    }
+                    Some(Lookup::Pending) => {
+                        eprintln!("{} waits for a query of the same tree", commit);
+                        None
+                    }
+                    Some(Lookup::Miss(tree)) => Some(tree),
+                    None => Some(None),
+                };
+
+                if let Some(tree) = tree {
+                    if let Err(err) = dispatch(
+                        commit,
+                        tree,
+                        &mut pool,
+                        &send,
+                        repository,
+                        test,
+                        &options,
+                        &output_directory,
+                    ) {
+                        eprintln!("{}", err);
+                        break;
+                    }
+                    stats.number_jobs += 1;
+                }
+            }
+            AlgorithmResponse::WaitForResult => {
                 wait = true;
 
+                //Known results can finish the search without a job.
                 if pool.active_processes.is_empty() {
-                    eprintln!("Algorithms suggests to wait, but there is nothing to wait for!");
+                    if !core.done() {
+                        eprintln!("Algorithms suggests to wait, but there is nothing to wait for!");
+                    }
                     break;
                 }
             }
-            crate::regression::AlgorithmResponse::InternalError(msg) => {
+            AlgorithmResponse::InternalError(msg) => {
                 eprintln!("{}", msg);
                 break;
             }
         };
 
+        if let Some((commit, result, tested)) = cached {
+            add_cached_result(commit, result, tested, core, &mut stats, &options);
+        }
+        update_status(control, core, &stats, &pool, &options, true);
+
         if wait || (pool.idle_processes.is_empty() && pool.empty_slots == 0) {
-            match recv_response(&recv, &mut pool) {
-                Ok(res) => {
-                    if !process_response(&res, core, &mut stats, &mut pool, &options, &mut log_data)
-                    {
+            match recv_response_until_cancelled(&recv, &mut pool, control) {
+                Ok(None) if control.is_cancelled() => break,
+                Ok(None) => {}
+                Ok(Some(res)) => {
+                    if !process_response(
+                        &res,
+                        core,
+                        &mut stats,
+                        &mut pool,
+                        &mut cache,
+                        &options,
+                        &mut log_data,
+                    ) {
                         break;
                     }
                 }
@@ -116,8 +339,15 @@ pub fn start<T: DVCS>(
         loop {
             match try_recv_response(&recv, &mut pool) {
                 Ok(res) => {
-                    if !process_response(&res, core, &mut stats, &mut pool, &options, &mut log_data)
-                    {
+                    if !process_response(
+                        &res,
+                        core,
+                        &mut stats,
+                        &mut pool,
+                        &mut cache,
+                        &options,
+                        &mut log_data,
+                    ) {
                         stop = true;
                         break;
                     }
@@ -139,34 +369,45 @@ pub fn start<T: DVCS>(
     } //END LOOP
 
     let overall_execution_time = start_time.elapsed();
+    let was_cancelled = cancelled.load(Ordering::SeqCst);
 
     //Wait for active processes to be done and clean up.
     eprintln!("Wait for active processes to finish!");
-    if options.do_interrupt {
-        for process in pool.active_processes.values_mut() {
-            process.interrupt();
+    if options.do_interrupt || was_cancelled {
+        let running = pool.commit_to_process.keys().cloned().collect::<Vec<_>>();
+        for commit in running {
+            interrupt(&commit, &mut pool, &options);
         }
     }
 
     while !pool.active_processes.is_empty() {
         let _ = recv_response(&recv, &mut pool);
     }
-    for process in pool.idle_processes {
+    for process in &pool.idle_processes {
         process.clean_up();
     }
 
     let points = core.results();
-    if let Some(log_location) = options.log_location {
+    let intervals = if was_cancelled {
+        core.intervals()
+    } else {
+        vec![]
+    };
+    if let Some(log_location) = options.log_location.as_ref() {
         log::write_summary(
             &overall_execution_time,
-            &points,
-            &log_location,
-            &mut log_data,
+            was_cancelled,
+            &intervals,
+            log_location,
+            &log_data,
         );
     }
 
     println!("---- STATS ----\n");
     println!("Commits tested: {}", stats.number_jobs);
+    if cache.is_some() {
+        println!("Results from cache: {}", stats.cached_results);
+    }
     println!("Regression points: {}", points.len());
     println!(
         "Runtime (seconds): {}",
@@ -176,24 +417,98 @@ pub fn start<T: DVCS>(
 
     for point in points {
         println!("Target: {}", point.target);
-        println!("Regression Point: {}", point.regression_point);
-        if let Some(message) = T::get_commit_info(repository, &point.regression_point) {
-            println!("{}", message);
+        if point.is_ambiguous() {
+            println!(
+                "{}: ambiguous, there are only skipped commits left",
+                options.terms.point_label()
+            );
+            println!("Candidates:");
+            for candidate in &point.candidates {
+                match T::get_commit_info(repository, candidate) {
+                    Some(message) => print!("{}", message),
+                    None => println!("{}", candidate),
+                }
+            }
+        } else {
+            println!(
+                "{}: {}",
+                options.terms.point_label(),
+                point.regression_point
+            );
+            if let Some(message) = T::get_commit_info(repository, &point.regression_point) {
+                println!("{}", message);
+            }
+        }
+        if !point.probabilities.is_empty() {
+            println!("Probabilities:");
+            for candidate in &point.probabilities {
+                println!("{:.3} {}", candidate.probability, candidate.commit);
+            }
         }
+        output::print_failure_output::<T>(
+            repository,
+            &point,
+            output_directory.path(),
+            &stats.results,
+            &stats.origins,
+            &options.sources,
+            options.output_tail,
+            options.diff_output,
+            &options.terms,
+        );
         println!("----");
     }
+    output_directory.remove();
+
+    if was_cancelled {
+        println!("\n---- CANCELLED ----\n");
+        for interval in intervals {
+            println!("Target: {}", interval.target);
+            for last_good in &interval.last_good {
+                println!("Last known {}: {}", options.terms.old, last_good);
+            }
+            println!("First known {}: {}", options.terms.new, interval.first_bad);
+            if let Some(message) = T::get_commit_info(repository, &interval.first_bad) {
+                println!("{}", message);
+            }
+            println!("----");
+        }
+    }
+
+    update_status(control, core, &stats, &pool, &options, false);
+    was_cancelled
+}
+
+//The first SIGINT/SIGTERM cancels the search, running processes are stopped
+//and the worktrees are removed. A second signal exits immediately.
+pub fn cancel_on_signal(control: &Control) {
+    let flag = control.cancelled.clone();
+
+    let handler = ctrlc::set_handler(move || {
+        if flag.swap(true, Ordering::SeqCst) {
+            eprintln!("Exit without clean up!");
+            std::process::exit(130);
+        }
+        eprintln!("Cancel search, press Ctrl-C again to exit immediately!");
+    });
+
+    if let Err(err) = handler {
+        eprintln!("Couldn't register signal handler: {}", err);
+    }
 }
 
-fn process_response<'a, T: DVCS>(
+fn process_response<T: DVCS>(
     response: &ProcessResponse,
     core: &mut dyn RegressionAlgorithm,
     stats: &mut Stats,
-    pool: &'a mut ProcessPool<T>,
+    pool: &mut ProcessPool<T>,
+    cache: &mut Option<TreeCache>,
     options: &Options,
     log_data: &mut TemporalLogData,
 ) -> bool {
+    let response = &classify(response, options);
     if let Some(path) = options.log_location.clone() {
-        log::add_result(response, &path, log_data);
+        log::add_result(response, &path, log_data, &options.terms);
     }
 
     match response.result.clone() {
@@ -201,19 +516,37 @@ fn process_response<'a, T: DVCS>(
             eprintln!(
                 "   Query finished:
     - {} 
-    - {} 
+    - {} ({})
     - {}s
 ----",
                 response.commit,
                 result,
+                options.terms.name(&result),
                 data.all.as_secs_f32(),
             );
-            core.add_result(response.commit.clone(), result);
+            if let Some(path) = options.bisect_log.as_ref() {
+                bisect_log::append(path, &response.commit, &result, &options.terms);
+            }
+            core.add_result(response.commit.clone(), result.clone());
+            stats
+                .results
+                .insert(response.commit.clone(), result.clone());
+
+            if let Some(c) = cache.as_mut() {
+                for commit in c.insert(&response.commit, result.clone()) {
+                    let tested = response.commit.clone();
+                    add_cached_result(commit, result.clone(), tested, core, stats, options);
+                }
+            }
         }
         Err(err) => match err {
             ProcessError::Interrupt => {
                 eprintln!("{} interrupted", response.commit);
                 stats.interrupted_tests += 1;
+
+                if let Some(c) = cache.as_mut() {
+                    c.abort(&response.commit);
+                }
             }
             ProcessError::Code => {
                 eprintln!("{} stops execution via exit code", response.commit);
@@ -228,23 +561,271 @@ fn process_response<'a, T: DVCS>(
 
     if options.do_interrupt {
         for commit in core.interrupts() {
-            interrupt(&commit, pool);
+            if let Some(c) = cache.as_mut() {
+                c.cancel(&commit);
+            }
+            interrupt(&commit, pool, options);
         }
     }
 
     true
 }
 
+fn add_cached_result(
+    commit: String,
+    result: TestResult,
+    tested: String,
+    core: &mut dyn RegressionAlgorithm,
+    stats: &mut Stats,
+    options: &Options,
+) {
+    if let Some(path) = options.log_location.as_ref() {
+        log::write_event(
+            path,
+            Event::CachedResult {
+                commit: &commit,
+                result: &result,
+                term: options.terms.name(&result),
+            },
+        );
+    }
+
+    eprintln!(
+        "   Cached result:
+    - {}
+    - {} ({})
+----",
+        commit,
+        result,
+        options.terms.name(&result),
+    );
+    stats.cached_results += 1;
+    if let Some(path) = options.bisect_log.as_ref() {
+        bisect_log::append(path, &commit, &result, &options.terms);
+    }
+    stats.results.insert(commit.clone(), result.clone());
+    stats.origins.insert(commit.clone(), Origin::Cache(tested));
+    core.add_result(commit, result);
+}
+
+//Suspects that got a result in the meantime, e.g. by the daemon, are skipped.
+fn next_suspect<T>(
+    suspects: &mut VecDeque<String>,
+    stats: &Stats,
+    pool: &ProcessPool<T>,
+) -> Option<String> {
+    while let Some(commit) = suspects.pop_front() {
+        if !stats.results.contains_key(&commit) && !pool.commit_to_process.contains_key(&commit) {
+            eprintln!("Testing suspect {}", commit);
+            return Some(commit);
+        }
+    }
+    None
+}
+
+fn add_imported_result(
+    commit: &str,
+    result: &TestResult,
+    core: &mut dyn RegressionAlgorithm,
+    stats: &mut Stats,
+    options: &Options,
+) {
+    if let Some(path) = options.log_location.as_ref() {
+        log::write_event(
+            path,
+            Event::ImportedResult {
+                commit,
+                result,
+                term: options.terms.name(result),
+            },
+        );
+    }
+
+    eprintln!(
+        "   Imported result:
+    - {}
+    - {} ({})
+----",
+        commit,
+        result,
+        options.terms.name(result),
+    );
+    if let Some(path) = options.bisect_log.as_ref() {
+        bisect_log::append(path, commit, result, &options.terms);
+    }
+    stats.results.insert(commit.to_string(), result.clone());
+    stats.origins.insert(commit.to_string(), Origin::Known);
+    core.add_result(commit.to_string(), result.clone());
+}
+
+//A successful test with a metric is good or bad depending on its samples.
+//Afterwards the result is mapped to the terms of the search. Manual results
+//are already in the terms of the search.
+fn classify(response: &ProcessResponse, options: &Options) -> ProcessResponse {
+    let result = match (&options.metric, &response.result) {
+        (Some(metric), Ok((TestResult::True, data))) => {
+            Ok((metric.classify(&data.metrics), data.clone()))
+        }
+        (_, result) => result.clone(),
+    };
+    ProcessResponse {
+        result: result.map(|(result, data)| match options.manual {
+            Some(_) => (result, data),
+            None => (options.terms.map(result), data),
+        }),
+        ..response.clone()
+    }
+}
+
+//Runs the test on all sources, their samples are the reference for relative
+//changes of the metric.
+#[allow(clippy::too_many_arguments)]
+fn measure_baseline<T: DVCS>(
+    pool: &mut ProcessPool<T>,
+    send: &mpsc::Sender<ProcessResponse>,
+    recv: &mpsc::Receiver<ProcessResponse>,
+    repository: &str,
+    test: &[String],
+    options: &Options,
+    output_directory: &OutputDirectory,
+    control: &Control,
+    log_data: &mut TemporalLogData,
+) -> Result<Vec<f64>, String> {
+    eprintln!("Measuring baseline ...");
+    let mut pending = options.sources.clone();
+    let mut running = 0;
+    let mut baseline = vec![];
+
+    while !pending.is_empty() || running > 0 {
+        while !pending.is_empty() && (!pool.idle_processes.is_empty() || pool.empty_slots > 0) {
+            let commit = pending.pop().unwrap();
+            let tree = T::tree_hash(repository, &commit);
+            dispatch(
+                commit,
+                tree,
+                pool,
+                send,
+                repository,
+                test,
+                options,
+                output_directory,
+            )?;
+            running += 1;
+        }
+
+        let response = match recv_response_until_cancelled(recv, pool, control)
+            .map_err(|err| err.to_string())?
+        {
+            Some(response) => response,
+            None if control.is_cancelled() => return Err("search was cancelled".to_string()),
+            None => continue,
+        };
+        running -= 1;
+        if let Some(path) = options.log_location.as_ref() {
+            log::add_result(&response, path, log_data, &options.terms);
+        }
+
+        match response.result {
+            Ok((TestResult::True, data)) => baseline.extend(data.metrics),
+            Ok((result, _)) => {
+                return Err(format!(
+                    "source {} has the result {}",
+                    response.commit, result
+                ))
+            }
+            Err(err) => return Err(format!("source {}: {}", response.commit, err)),
+        }
+    }
+
+    if let Some(path) = options.log_location.as_ref() {
+        log::write_event(
+            path,
+            Event::BaselineMeasured {
+                commits: &options.sources,
+                mean: metric::mean(&baseline),
+                metrics: &baseline,
+            },
+        );
+    }
+    eprintln!("Baseline: {} ({:?})", metric::mean(&baseline), baseline);
+    Ok(baseline)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dispatch<T: DVCS>(
+    commit: String,
+    tree: Option<String>,
+    pool: &mut ProcessPool<T>,
+    send: &mpsc::Sender<ProcessResponse>,
+    repository: &str,
+    test: &[String],
+    options: &Options,
+    output_directory: &OutputDirectory,
+) -> Result<(), String> {
+    let build = options.build_script.as_ref().map(|script| BuildStep {
+        script: script.clone(),
+        directory: options
+            .build_location
+            .join(tree.as_ref().unwrap_or(&commit)),
+        reuse: tree.is_some(),
+    });
+    let setup_time = Instant::now();
+    let process = load_process(
+        pool,
+        repository,
+        options.worktree_location.clone(),
+        &options.hooks,
+        &options.verdicts,
+        &options.metric,
+        &options.manual,
+        &commit,
+    )
+    .map_err(|err| format!("Couldn't prepare process for {}: {}", commit, err))?;
+
+    if let Some(path) = options.log_location.as_ref() {
+        log::write_event(
+            path,
+            Event::JobDispatched {
+                pid: process.id,
+                commit: &commit,
+                tree: tree.as_deref(),
+            },
+        );
+    }
+    process.run(
+        commit,
+        send.clone(),
+        test.to_vec(),
+        build,
+        setup_time,
+        output_directory.path(),
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 fn load_process<'a, T: DVCS>(
     pool: &'a mut ProcessPool<T>,
     repository: &str,
     worktree_location: Option<String>,
+    hooks: &Hooks,
+    verdicts: &Verdicts,
+    metric: &Option<Metric>,
+    manual: &Option<Marks>,
     commit: &str,
-) -> &'a mut LocalProcess<T> {
+) -> Result<&'a mut LocalProcess<T>, ProcessError> {
     let available_process = if !pool.idle_processes.is_empty() {
         pool.idle_processes.pop().unwrap()
     } else if pool.empty_slots > 0 {
-        let process = LocalProcess::new(pool.next_id, repository, worktree_location);
+        let process = LocalProcess::new(
+            pool.next_id,
+            repository,
+            worktree_location,
+            hooks.clone(),
+            verdicts.clone(),
+            metric.clone(),
+            manual.clone(),
+        )?;
         pool.next_id += 1;
         pool.empty_slots -= 1;
         process
@@ -255,7 +836,7 @@ fn load_process<'a, T: DVCS>(
     let id = available_process.id;
     pool.commit_to_process.insert(commit.to_string(), id);
     pool.active_processes.insert(id, available_process);
-    pool.active_processes.get_mut(&id).unwrap()
+    Ok(pool.active_processes.get_mut(&id).unwrap())
 }
 
 fn try_recv_response<T: DVCS>(
@@ -276,6 +857,29 @@ fn recv_response<T: DVCS>(
     Ok(res)
 }
 
+//Same as `recv_response`, but returns `None` as soon as the search is
+//cancelled or there are requests of the control.
+fn recv_response_until_cancelled<T: DVCS>(
+    recv: &mpsc::Receiver<ProcessResponse>,
+    pool: &mut ProcessPool<T>,
+    control: &Control,
+) -> Result<Option<ProcessResponse>, RecvError> {
+    loop {
+        match recv.recv_timeout(CANCEL_POLL_INTERVAL) {
+            Ok(res) => {
+                deactivate_process(res.pid, &res.commit, pool);
+                return Ok(Some(res));
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if control.is_cancelled() || control.has_requests() {
+                    return Ok(None);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return Err(RecvError),
+        }
+    }
+}
+
 fn deactivate_process<T: DVCS>(id: u32, commit: &str, pool: &mut ProcessPool<T>) {
     let process = pool
         .active_processes
@@ -283,12 +887,101 @@ fn deactivate_process<T: DVCS>(id: u32, commit: &str, pool: &mut ProcessPool<T>)
         .unwrap_or_else(|| panic!("Couldn't find process {} in pool of active processes!", id));
     pool.commit_to_process.remove(commit);
     pool.interrupted_processes.remove(&id);
-    pool.idle_processes.push(process);
+    if pool.surplus > 0 {
+        pool.surplus -= 1;
+        process.clean_up();
+    } else {
+        pool.idle_processes.push(process);
+    }
 }
 
-fn interrupt<T: DVCS>(commit: &str, pool: &mut ProcessPool<T>) {
+//Adds and removes slots until the pool has the given number of processes.
+//Busy processes are removed after their query.
+fn resize<T: DVCS>(pool: &mut ProcessPool<T>, processes: u32) {
+    if processes >= pool.processes {
+        let mut missing = processes - pool.processes;
+        let cancelled = missing.min(pool.surplus);
+        pool.surplus -= cancelled;
+        missing -= cancelled;
+        pool.empty_slots += missing;
+    } else {
+        let mut excess = pool.processes - processes;
+        let slots = excess.min(pool.empty_slots);
+        pool.empty_slots -= slots;
+        excess -= slots;
+        while excess > 0 {
+            match pool.idle_processes.pop() {
+                Some(process) => process.clean_up(),
+                None => break,
+            }
+            excess -= 1;
+        }
+        pool.surplus += excess;
+    }
+    pool.processes = processes;
+}
+
+fn apply_requests<T: DVCS>(
+    control: &Control,
+    core: &mut dyn RegressionAlgorithm,
+    stats: &mut Stats,
+    pool: &mut ProcessPool<T>,
+    options: &Options,
+) {
+    let (processes, results) = {
+        let mut state = control.state.lock().unwrap();
+        (state.processes.take(), std::mem::take(&mut state.results))
+    };
+
+    if let Some(processes) = processes {
+        eprintln!("Use {} processes", processes);
+        resize(pool, processes);
+    }
+    for (commit, result) in results {
+        if stats.results.contains_key(&commit) {
+            eprintln!("{} has already a result", commit);
+            continue;
+        }
+        interrupt(&commit, pool, options);
+        add_imported_result(&commit, &result, core, stats, options);
+    }
+}
+
+fn update_status<T: DVCS>(
+    control: &Control,
+    core: &dyn RegressionAlgorithm,
+    stats: &Stats,
+    pool: &ProcessPool<T>,
+    options: &Options,
+    running: bool,
+) {
+    let status = Status {
+        running,
+        cancelled: control.is_cancelled(),
+        processes: pool.processes,
+        tested: stats.number_jobs,
+        active: pool
+            .commit_to_process
+            .iter()
+            .map(|(commit, id)| (commit.clone(), *id))
+            .collect(),
+        path: core.current_path(),
+        results: stats
+            .results
+            .iter()
+            .map(|(commit, result)| (commit.clone(), options.terms.name(result).to_string()))
+            .collect(),
+        regression_points: core.results(),
+    };
+    control.state.lock().unwrap().status = status;
+}
+
+fn interrupt<T: DVCS>(commit: &str, pool: &mut ProcessPool<T>, options: &Options) {
     let id_ = pool.commit_to_process.get(commit);
     if let Some(id) = id_ {
+        if let Some(path) = options.log_location.as_ref() {
+            log::write_event(path, Event::InterruptIssued { pid: *id, commit });
+        }
         pool.interrupted_processes.insert(*id);
         let process_ = pool.active_processes.get_mut(id);
         if let Some(process) = process_ {
@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{regression::TestResult, terms::Terms};
+
+//How often a waiting process checks for a mark.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Socket of a manual search in `repository`.
+pub fn socket_path(repository: &Path) -> PathBuf {
+    repository.join(".crs").join("manual.sock")
+}
+
+/// Results of commits that are judged by the user. Processes register their
+/// commit once it is checked out and wait until it is marked.
+#[derive(Clone)]
+pub struct Marks {
+    state: Arc<Mutex<State>>,
+    terms: Terms,
+}
+
+#[derive(Default)]
+struct State {
+    //Checked out commits that wait for a mark, in the order they got ready.
+    pending: Vec<String>,
+    marked: HashMap<String, TestResult>,
+}
+
+impl Marks {
+    pub fn new(terms: Terms) -> Self {
+        Marks {
+            state: Arc::new(Mutex::new(State::default())),
+            terms,
+        }
+    }
+
+    /// Waits until the user marks `commit`. Returns `None` if the query was
+    /// interrupted in the meantime.
+    pub fn wait(
+        &self,
+        commit: &str,
+        worktree: &str,
+        interrupt_receiver: &mpsc::Receiver<()>,
+    ) -> Option<TestResult> {
+        self.state.lock().unwrap().pending.push(commit.to_string());
+        println!(
+            "{} is ready in {}, mark it as {}, {} or skip",
+            commit, worktree, self.terms.old, self.terms.new
+        );
+
+        loop {
+            if interrupt_receiver.try_recv().is_ok() {
+                self.state.lock().unwrap().pending.retain(|c| c != commit);
+                return None;
+            }
+            if let Some(result) = self.state.lock().unwrap().marked.remove(commit) {
+                return Some(result);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Marks a pending commit, the input looks like `<COMMIT> <VERDICT>`. The
+    /// commit may be abbreviated and can be left out, if only one commit is
+    /// pending.
+    pub fn mark(&self, input: &str) -> Result<String, String> {
+        let words = input.split_whitespace().collect::<Vec<_>>();
+        let (commit, verdict) = match words.as_slice() {
+            [verdict] => (None, *verdict),
+            [commit, verdict] => (Some(*commit), *verdict),
+            _ => return Err("expected <COMMIT> <VERDICT>".to_string()),
+        };
+        let result = self.terms.parse(verdict)?;
+
+        let mut state = self.state.lock().unwrap();
+        let candidates = state
+            .pending
+            .iter()
+            .filter(|pending| commit.map(|c| pending.starts_with(c)).unwrap_or(true))
+            .cloned()
+            .collect::<Vec<_>>();
+        let commit = match candidates.as_slice() {
+            [commit] => commit.clone(),
+            [] => return Err("no such commit is waiting for a mark".to_string()),
+            _ => return Err("several commits are waiting, name the commit".to_string()),
+        };
+
+        state.pending.retain(|c| c != &commit);
+        state.marked.insert(commit.clone(), result);
+        Ok(commit)
+    }
+}
+
+/// Accepts marks from stdin and from the socket, until the search is over.
+pub fn listen(marks: &Marks, socket: &Path) -> Result<(), String> {
+    if let Some(directory) = socket.parent() {
+        fs::create_dir_all(directory).map_err(|err| err.to_string())?;
+    }
+    //A socket of a previous search that wasn't cleaned up.
+    let _ = fs::remove_file(socket);
+    let listener = UnixListener::bind(socket)
+        .map_err(|err| format!("Couldn't listen on {}: {}", socket.display(), err))?;
+
+    let socket_marks = marks.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(&stream);
+            let mut line = String::new();
+            if reader.read_line(&mut line).is_err() {
+                continue;
+            }
+            let reply = match socket_marks.mark(&line) {
+                Ok(commit) => format!("marked {}", commit),
+                Err(err) => format!("error: {}", err),
+            };
+            let _ = writeln!(&stream, "{}", reply);
+        }
+    });
+
+    let stdin_marks = marks.clone();
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match stdin_marks.mark(&line) {
+                Ok(commit) => println!("Marked {}", commit),
+                Err(err) => println!("{}", err),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Marks a commit of a running manual search, used by `crs mark`.
+pub fn send(socket: &Path, commit: &str, verdict: &str) -> Result<String, String> {
+    let mut stream = UnixStream::connect(socket).map_err(|err| {
+        format!(
+            "Couldn't connect to {}, is a manual search running? ({})",
+            socket.display(),
+            err
+        )
+    })?;
+    writeln!(stream, "{} {}", commit, verdict).map_err(|err| err.to_string())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|err| err.to_string())?;
+    let reply = reply.trim();
+    match reply.strip_prefix("error: ") {
+        Some(err) => Err(err.to_string()),
+        None => Ok(reply.to_string()),
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::regression::TestResult;
+
+/// Where the test reports its metric.
+#[derive(Debug, Clone)]
+pub enum MetricSource {
+    /// Last non-empty line of stdout.
+    Stdout,
+    /// File in the worktree, that contains the number.
+    File(PathBuf),
+}
+
+impl FromStr for MetricSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "stdout" => Ok(MetricSource::Stdout),
+            Some(("file", path)) if !path.is_empty() => Ok(MetricSource::File(PathBuf::from(path))),
+            _ => Err(format!(
+                "unknown metric \"{}\", expected stdout or file:<PATH>",
+                s
+            )),
+        }
+    }
+}
+
+/// Classifies commits by a numeric metric instead of the exit code of the
+/// test, e.g. for performance regressions. A commit is bad if the mean of its
+/// samples exceeds the threshold, or if it is worse than the sources by more
+/// than `max_change` percent and the difference is significant.
+#[derive(Debug, Clone)]
+pub struct Metric {
+    pub source: MetricSource,
+    pub samples: u32,
+    pub threshold: Option<f64>,
+    pub max_change: Option<f64>,
+    pub higher_is_better: bool,
+    pub significance: f64,
+    /// Samples of the sources, the reference for `max_change`.
+    pub baseline: Vec<f64>,
+}
+
+impl Metric {
+    pub fn needs_baseline(&self) -> bool {
+        self.max_change.is_some()
+    }
+
+    /// Reads the metric of a single run of the test.
+    pub fn read(&self, worktree: &str, stdout: &Path) -> Result<f64, String> {
+        let path = match &self.source {
+            MetricSource::Stdout => stdout.to_path_buf(),
+            MetricSource::File(file) => Path::new(worktree).join(file),
+        };
+        let content = fs::read_to_string(&path)
+            .map_err(|err| format!("couldn't read metric from {}: {}", path.display(), err))?;
+        let line = content
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default();
+
+        line.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("\"{}\" in {} is not a number", line.trim(), path.display()))
+    }
+
+    pub fn classify(&self, samples: &[f64]) -> TestResult {
+        let value = mean(samples);
+        //Positive values of `worse` are regressions.
+        let worse = |a: f64, b: f64| if self.higher_is_better { b - a } else { a - b };
+
+        if let Some(threshold) = self.threshold {
+            if worse(value, threshold) > 0.0 {
+                return TestResult::False;
+            }
+        }
+
+        if let Some(max_change) = self.max_change {
+            let reference = mean(&self.baseline);
+            let change = worse(value, reference) / reference.abs() * 100.0;
+            if change > max_change && self.is_significant(samples) {
+                return TestResult::False;
+            }
+        }
+
+        TestResult::True
+    }
+
+    //One-sided Welch's t-test, whether the samples are worse than the
+    //baseline. With less than two values on either side, every change counts.
+    fn is_significant(&self, samples: &[f64]) -> bool {
+        if samples.len() < 2 || self.baseline.len() < 2 {
+            return true;
+        }
+
+        let (n1, n2) = (samples.len() as f64, self.baseline.len() as f64);
+        let (v1, v2) = (variance(samples) / n1, variance(&self.baseline) / n2);
+        let mut difference = mean(samples) - mean(&self.baseline);
+        if self.higher_is_better {
+            difference = -difference;
+        }
+
+        if v1 + v2 == 0.0 {
+            return difference > 0.0;
+        }
+
+        let t = difference / (v1 + v2).sqrt();
+        let df = (v1 + v2).powi(2) / (v1.powi(2) / (n1 - 1.0) + v2.powi(2) / (n2 - 1.0));
+        let p = 1.0 - student_t_cdf(t, df);
+        p < self.significance
+    }
+}
+
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn variance(values: &[f64]) -> f64 {
+    let m = mean(values);
+    values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (values.len() as f64 - 1.0)
+}
+
+fn student_t_cdf(t: f64, df: f64) -> f64 {
+    let tail = 0.5 * incomplete_beta(df / 2.0, 0.5, df / (df + t * t));
+    if t > 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+//Regularized incomplete beta function I_x(a, b), evaluated with a continued
+//fraction (Numerical Recipes, 6.4).
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-30;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut result = d;
+
+    for m in 1..200 {
+        let m = m as f64;
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            result *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    result
+}
+
+//Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000000000190015;
+    for (i, coefficient) in COEFFICIENTS.iter().enumerate() {
+        series += coefficient / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    dvcs::DVCS,
+    log,
+    regression::{RegressionPoint, TestResult},
+    terms::Terms,
+};
+
+/// Directory for the stdout and stderr of all queries. Without a log
+/// directory, a temporary directory is used, which is removed after the search.
+pub struct OutputDirectory {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl OutputDirectory {
+    pub fn new(log_location: Option<&Path>) -> Self {
+        match log_location {
+            Some(path) => OutputDirectory {
+                path: log::output_path(path),
+                temporary: false,
+            },
+            None => {
+                let path = std::env::temp_dir().join(format!("crs_output_{}", std::process::id()));
+                fs::create_dir_all(&path).expect("Couldn't create temporary output directory!");
+                OutputDirectory {
+                    path,
+                    temporary: true,
+                }
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn remove(self) {
+        if self.temporary {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+/// Origin of a result that was not queried for the commit itself.
+pub enum Origin {
+    /// The result of the given commit with the same tree.
+    Cache(String),
+    /// Imported or added from outside of the search.
+    Known,
+}
+
+/// Files for the stdout and stderr of the test of a commit.
+pub fn query_output(directory: &Path, commit: &str) -> (PathBuf, PathBuf) {
+    (
+        directory.join(format!("{}_stdout", commit)),
+        directory.join(format!("{}_stderr", commit)),
+    )
+}
+
+/// Prints the end of the output of the first new commit and of its last old
+/// parents, so the reason of the regression is visible without another run.
+/// Sources and targets are not tested, their states are assumed.
+#[allow(clippy::too_many_arguments)]
+pub fn print_failure_output<T: DVCS>(
+    repository: &str,
+    point: &RegressionPoint,
+    directory: &Path,
+    results: &HashMap<String, TestResult>,
+    origins: &HashMap<String, Origin>,
+    sources: &[String],
+    lines: usize,
+    show_diff: bool,
+    terms: &Terms,
+) {
+    let first_bad = &point.regression_point;
+    let last_good = last_good_parents::<T>(repository, first_bad, results, sources);
+    let tested = |commit: &str| {
+        if let Some(Origin::Cache(tested)) = origins.get(commit) {
+            return Ok(tested.clone());
+        }
+        let (stdout, stderr) = query_output(directory, commit);
+        if stdout.is_file() || stderr.is_file() {
+            Ok(commit.to_string())
+        } else if let Some(Origin::Known) = origins.get(commit) {
+            Err("its result was known, it was not tested".to_string())
+        } else if sources.iter().any(|source| source == commit) {
+            Err(format!(
+                "it is a source, assumed {} and not tested",
+                terms.old
+            ))
+        } else if *commit == point.target {
+            Err(format!(
+                "it is a target, assumed {} and not tested",
+                terms.new
+            ))
+        } else {
+            Ok(commit.to_string())
+        }
+    };
+
+    if lines > 0 {
+        let name = format!("first {}", terms.new);
+        print_tail(directory, first_bad, tested(first_bad), &name, lines);
+        if last_good.is_empty() {
+            println!("No {} parent of {}", terms.old, short(first_bad));
+        }
+        for commit in &last_good {
+            let name = format!("last {}", terms.old);
+            print_tail(directory, commit, tested(commit), &name, lines);
+        }
+    }
+
+    if show_diff {
+        let first_bad_tested = tested(first_bad);
+        for commit in &last_good {
+            let (first_bad, commit) = match (&first_bad_tested, tested(commit)) {
+                (Ok(first_bad), Ok(commit)) => (first_bad, commit),
+                _ => {
+                    println!(
+                        "-- no diff of {} and {}, both have to be tested --",
+                        short(commit),
+                        short(first_bad)
+                    );
+                    continue;
+                }
+            };
+            let (bad_stdout, bad_stderr) = query_output(directory, first_bad);
+            let (good_stdout, good_stderr) = query_output(directory, &commit);
+            for (name, good, bad) in [
+                ("stdout", good_stdout, &bad_stdout),
+                ("stderr", good_stderr, &bad_stderr),
+            ] {
+                println!(
+                    "-- diff of {} ({} -> {}) --",
+                    name,
+                    short(&commit),
+                    short(first_bad)
+                );
+                match diff(&good, bad, name, terms) {
+                    Some(diff) if diff.is_empty() => println!("(identical)"),
+                    Some(diff) => print!("{}", diff),
+                    None => println!("(couldn't compare outputs)"),
+                }
+            }
+        }
+    }
+}
+
+//Parents of the regression point that are good, by a result or as a source.
+//Skipped parents are replaced by their own parents.
+fn last_good_parents<T: DVCS>(
+    repository: &str,
+    commit: &str,
+    results: &HashMap<String, TestResult>,
+    sources: &[String],
+) -> Vec<String> {
+    let mut good = vec![];
+    let mut queue = VecDeque::from([commit.to_string()]);
+    let mut visited = HashSet::new();
+
+    while let Some(current) = queue.pop_front() {
+        for parent in T::parents(repository, &current).unwrap_or_default() {
+            if !visited.insert(parent.clone()) {
+                continue;
+            }
+            match results.get(&parent) {
+                Some(TestResult::True) => good.push(parent),
+                Some(TestResult::Ignore) => queue.push_back(parent),
+                None if sources.contains(&parent) => good.push(parent),
+                _ => {}
+            }
+        }
+    }
+    good
+}
+
+//`tested` is the commit whose query has the output, or why there is none.
+fn print_tail(
+    directory: &Path,
+    commit: &str,
+    tested: Result<String, String>,
+    name: &str,
+    lines: usize,
+) {
+    let tested = match tested {
+        Ok(tested) => tested,
+        Err(reason) => {
+            println!(
+                "-- no output of {} commit {}: {} --",
+                name,
+                short(commit),
+                reason
+            );
+            return;
+        }
+    };
+    if tested != commit {
+        println!(
+            "-- {} commit {} has the result of {} with the same tree --",
+            name,
+            short(commit),
+            short(&tested)
+        );
+    }
+    let (stdout, stderr) = query_output(directory, &tested);
+    let mut empty = true;
+    for (stream, path) in [("stdout", stdout), ("stderr", stderr)] {
+        if let Some(text) = tail(&path, lines).filter(|text| !text.is_empty()) {
+            println!(
+                "-- {} of {} commit {} (last {} lines) --",
+                stream,
+                name,
+                short(commit),
+                lines
+            );
+            println!("{}", text);
+            empty = false;
+        }
+    }
+    if empty {
+        println!("-- no output of {} commit {} --", name, short(commit));
+    }
+}
+
+/// The last lines of a file.
+pub fn tail(path: &Path, lines: usize) -> Option<String> {
+    let content = fs::read(path).ok()?;
+    let content = String::from_utf8_lossy(&content);
+    let all = content.lines().collect::<Vec<_>>();
+    Some(all[all.len().saturating_sub(lines)..].join("\n"))
+}
+
+fn diff(good: &Path, bad: &Path, name: &str, terms: &Terms) -> Option<String> {
+    if !good.is_file() || !bad.is_file() {
+        return None;
+    }
+    let output = Command::new("diff")
+        .arg("-u")
+        .args(["--label", &format!("{}/{}", terms.old, name)])
+        .args(["--label", &format!("{}/{}", terms.new, name)])
+        .arg(good)
+        .arg(bad)
+        .output()
+        .ok()?;
+
+    //diff exits with 1 if the files differ.
+    match output.status.code() {
+        Some(0) | Some(1) => Some(String::from_utf8_lossy(&output.stdout).to_string()),
+        _ => None,
+    }
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
@@ -1,7 +1,13 @@
-use crate::dvcs::{run_script_async, Worktree, DVCS};
-use crate::log::output_path;
+use crate::dvcs::{run_command_sync, run_script_async, Worktree, DVCS};
+use crate::manual::Marks;
+use crate::metric::Metric;
+use crate::output::query_output;
 use crate::regression::TestResult;
+use crate::verdict::{Verdict, Verdicts};
+use std::fs;
 use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus};
 use std::sync::mpsc;
 use std::time::{Duration, Instant};
 use std::{fmt, thread};
@@ -10,6 +16,10 @@ use std::{fmt, thread};
 pub enum ProcessError {
     DVCSError(String),
     ScriptError(String),
+    SetupError(String),
+    PreQueryError(String),
+    BuildError(String),
+    PostQueryError(String),
     TimeError,
     Code,
     Interrupt,
@@ -20,6 +30,12 @@ impl fmt::Display for ProcessError {
         match self {
             crate::process::ProcessError::DVCSError(s) => write!(f, "DVCS Error {}", s),
             crate::process::ProcessError::ScriptError(s) => write!(f, "Script Error {}", s),
+            crate::process::ProcessError::SetupError(s) => write!(f, "Setup Error {}", s),
+            crate::process::ProcessError::PreQueryError(s) => write!(f, "Pre-Query Error {}", s),
+            crate::process::ProcessError::BuildError(s) => write!(f, "Build Error {}", s),
+            crate::process::ProcessError::PostQueryError(s) => {
+                write!(f, "Post-Query Error {}", s)
+            }
             crate::process::ProcessError::TimeError => write!(f, "Time Error"),
             crate::process::ProcessError::Code => write!(f, "Exit Code"),
             crate::process::ProcessError::Interrupt => write!(f, "Interrupt"),
@@ -32,8 +48,33 @@ pub struct ExecutionData {
     pub setup: Duration,
     pub query: Duration,
     pub all: Duration,
+    /// Metric of each sample, if the test reports a metric.
+    pub metrics: Vec<f64>,
+}
+
+/// Optional scripts that are executed around the queries of a process. All
+/// hooks are executed in the root directory of the worktree.
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    /// Executed once, after the worktree of a process has been created.
+    pub worktree_setup: Option<String>,
+    /// Executed before each query, after the commit has been checked out.
+    pub pre_query: Option<String>,
+    /// Executed after each query, also if the query was interrupted.
+    pub post_query: Option<String>,
+}
+
+/// Optional build step that is executed before the test. The build and the
+/// test script can access the build directory via `CRS_BUILD_DIR`.
+#[derive(Debug, Clone)]
+pub struct BuildStep {
+    pub script: String,
+    pub directory: PathBuf,
+    /// Reuse the outputs of a previous successful build in `directory`.
+    pub reuse: bool,
 }
 
+#[derive(Clone)]
 pub struct ProcessResponse {
     pub pid: u32,
     pub commit: String,
@@ -43,37 +84,73 @@ pub struct ProcessResponse {
 pub struct LocalProcess<S> {
     pub id: u32,
     pub worktree: Worktree,
+    hooks: Hooks,
+    verdicts: Verdicts,
+    metric: Option<Metric>,
+    /// The user judges the commits instead of a test script.
+    manual: Option<Marks>,
     interrupt_transmitter: Option<mpsc::Sender<()>>,
     _marker: PhantomData<S>,
 }
 
 impl<S: DVCS> LocalProcess<S> {
-    pub fn new(id: u32, repository: &str, external_location: Option<String>) -> Self {
+    pub fn new(
+        id: u32,
+        repository: &str,
+        external_location: Option<String>,
+        hooks: Hooks,
+        verdicts: Verdicts,
+        metric: Option<Metric>,
+        manual: Option<Marks>,
+    ) -> Result<Self, ProcessError> {
         let worktree = S::create_worktree(repository, &format!("crs_{}", id), external_location)
-            .unwrap_or_else(|_| panic!("Couldn't create worktree for {}!", id));
+            .map_err(|_| {
+                ProcessError::DVCSError(format!("Couldn't create worktree for {}!", id))
+            })?;
 
-        LocalProcess {
+        if let Some(script_path) = &hooks.worktree_setup {
+            if let Err(message) = run_hook(&worktree.location, script_path) {
+                if S::remove_worktree(&worktree).is_err() {
+                    eprintln!("Can not remove worktree of process {}", id);
+                }
+                return Err(ProcessError::SetupError(message));
+            }
+        }
+
+        Ok(LocalProcess {
             id,
             worktree,
+            hooks,
+            verdicts,
+            metric,
+            manual,
             interrupt_transmitter: None,
             _marker: PhantomData,
-        }
+        })
     }
 
     pub fn run(
         &mut self,
         commit: String,
         trans: mpsc::Sender<ProcessResponse>,
-        script_path: String,
+        test: Vec<String>,
+        build: Option<BuildStep>,
         setup_time: Instant,
-        log_directory: Option<&std::path::PathBuf>,
+        output_directory: &std::path::Path,
     ) {
         let id = self.id;
         let worktree = self.worktree.clone();
+        let hooks = self.hooks.clone();
+        let verdicts = self.verdicts.clone();
+        let metric = self.metric.clone();
+        let manual = self.manual.clone();
         let (interrupt_transmitter, interrupt_receiver) = mpsc::channel();
         self.interrupt_transmitter = Some(interrupt_transmitter);
-        let log_stdout = log_directory.map(|p| output_path(p).join(format!("{}_stdout", commit)));
-        let log_stderr = log_directory.map(|p| output_path(p).join(format!("{}_stderr", commit)));
+        let (log_stdout, log_stderr) = query_output(output_directory, &commit);
+        let log_build = Some((
+            output_directory.join(format!("{}_build_stdout", commit)),
+            output_directory.join(format!("{}_build_stderr", commit)),
+        ));
 
         thread::spawn(move || {
             if interrupt_receiver.try_recv().is_ok() {
@@ -113,82 +190,154 @@ impl<S: DVCS> LocalProcess<S> {
                 return;
             }
 
+            if let Some(pre_query) = &hooks.pre_query {
+                if let Err(message) = run_hook(&worktree.location, pre_query) {
+                    error(&trans, id, commit, ProcessError::PreQueryError(message));
+                    return;
+                }
+            }
+
+            let build_result = match &build {
+                Some(build) => {
+                    match run_build(build, &worktree.location, &interrupt_receiver, &log_build) {
+                        Ok(Some(success)) => Some(success),
+                        Ok(None) => {
+                            run_post_query(&hooks, &worktree);
+                            error(&trans, id, commit, ProcessError::Interrupt);
+                            return;
+                        }
+                        Err(err) => {
+                            run_post_query(&hooks, &worktree);
+                            error(&trans, id, commit, ProcessError::BuildError(err));
+                            return;
+                        }
+                    }
+                }
+                None => None,
+            };
+
             let after_setup_time = Instant::now();
 
-            let mut child =
-                match run_script_async(&worktree.location, &script_path, log_stdout, log_stderr) {
+            //A commit that can't be built is untestable. In manual mode the user
+            //judges the commit instead of the test. With a metric, the test is
+            //repeated for each sample, as long as it succeeds.
+            let mut metrics = vec![];
+            let mut verdict = Verdict::Result(TestResult::True);
+            let samples = metric.as_ref().map(|m| m.samples).unwrap_or(1);
+            if build_result == Some(false) {
+                verdict = Verdict::Result(TestResult::Ignore);
+            } else if let Some(marks) = &manual {
+                match marks.wait(&commit, &worktree.location, &interrupt_receiver) {
+                    Some(result) => verdict = Verdict::Result(result),
+                    None => {
+                        run_post_query(&hooks, &worktree);
+                        error(&trans, id, commit, ProcessError::Interrupt);
+                        return;
+                    }
+                }
+            }
+            for _ in 0..samples {
+                if manual.is_some() || verdict != Verdict::Result(TestResult::True) {
+                    break;
+                }
+
+                let envs = build_envs(build.as_ref());
+                let mut child = match run_script_async(
+                    &worktree.location,
+                    &test[0],
+                    &test[1..],
+                    Some(log_stdout.clone()),
+                    Some(log_stderr.clone()),
+                    &envs,
+                ) {
                     Ok(child) => child,
                     Err(err) => {
+                        run_post_query(&hooks, &worktree);
                         scerror(&trans, id, commit, err.to_string());
                         return;
                     }
                 };
 
-            loop {
-                let response = child.try_wait();
-
-                let op_code = match response {
-                    Ok(op_code) => op_code,
+                verdict = match wait_for(&mut child, &interrupt_receiver) {
+                    Ok(Some(status)) => verdicts.evaluate(&status, &log_stdout, &log_stderr),
+                    Ok(None) => {
+                        run_post_query(&hooks, &worktree);
+                        error(&trans, id, commit, ProcessError::Interrupt);
+                        return;
+                    }
                     Err(err) => {
-                        scerror(&trans, id, commit.clone(), err.to_string());
+                        run_post_query(&hooks, &worktree);
+                        scerror(&trans, id, commit, err);
                         return;
                     }
                 };
 
-                let code = match op_code {
-                    Some(code) => code.code().unwrap(),
-                    None => match interrupt_receiver.try_recv() {
-                        Ok(_) => {
-                            child.kill().expect("Terminating process killed!");
-                            error(&trans, id, commit, ProcessError::Interrupt);
+                if let (Some(metric), Verdict::Result(TestResult::True)) = (&metric, &verdict) {
+                    match metric.read(&worktree.location, &log_stdout) {
+                        Ok(value) => metrics.push(value),
+                        Err(err) => {
+                            run_post_query(&hooks, &worktree);
+                            scerror(&trans, id, commit, err);
                             return;
                         }
-                        Err(_) => continue,
-                    },
-                };
+                    }
+                }
+            }
 
-                let result = if code == 0 {
-                    TestResult::True
-                } else if code == 125 {
-                    TestResult::Ignore
-                } else if code >= 128 {
-                    cderror(&trans, id, commit);
+            let after_query_time = Instant::now();
+
+            if let Some(post_query) = &hooks.post_query {
+                if let Err(message) = run_hook(&worktree.location, post_query) {
+                    error(&trans, id, commit, ProcessError::PostQueryError(message));
                     return;
-                } else {
-                    TestResult::False
-                };
+                }
+            }
 
-                let after_query_time = Instant::now();
-                let checkout_duration = after_setup_time.checked_duration_since(setup_time);
-                let query_duration = after_query_time.checked_duration_since(after_setup_time);
-                let overall_duration = after_query_time.checked_duration_since(setup_time);
-
-                if let (Some(od), Some(cd), Some(qd)) =
-                    (checkout_duration, query_duration, overall_duration)
-                {
-                    let execution_time = ExecutionData {
-                        all: od,
-                        setup: cd,
-                        query: qd,
-                    };
-                    trans
-                        .send(ProcessResponse {
-                            pid: id,
-                            commit: commit.clone(),
-                            result: Ok((result, execution_time)),
-                        })
-                        .expect("transmitter broken!");
-                } else {
-                    error(&trans, id, commit.clone(), ProcessError::TimeError);
+            let result = match verdict {
+                Verdict::Result(result) => result,
+                Verdict::Abort => {
+                    cderror(&trans, id, commit);
+                    return;
                 }
-                break;
+            };
+
+            //Time spent in the hooks and the build is accounted to the setup.
+            let after_post_query_time = Instant::now();
+            let checkout_duration = after_setup_time.checked_duration_since(setup_time);
+            let query_duration = after_query_time.checked_duration_since(after_setup_time);
+            let post_query_duration =
+                after_post_query_time.checked_duration_since(after_query_time);
+            let overall_duration = after_post_query_time.checked_duration_since(setup_time);
+
+            if let (Some(od), Some(cd), Some(qd), Some(pd)) = (
+                overall_duration,
+                checkout_duration,
+                query_duration,
+                post_query_duration,
+            ) {
+                let execution_time = ExecutionData {
+                    all: od,
+                    setup: cd + pd,
+                    query: qd,
+                    metrics,
+                };
+                trans
+                    .send(ProcessResponse {
+                        pid: id,
+                        commit,
+                        result: Ok((result, execution_time)),
+                    })
+                    .expect("transmitter broken!");
+            } else {
+                error(&trans, id, commit, ProcessError::TimeError);
             }
         });
     }
 
     pub fn interrupt(&mut self) {
         if let Some(trans) = self.interrupt_transmitter.as_ref() {
-            trans.send(()).expect("transmitter broken!");
+            //A worker that already sent its result needs no interrupt.
+            let _ = trans.send(());
             self.interrupt_transmitter = None;
         }
     }
@@ -200,6 +349,136 @@ impl<S: DVCS> LocalProcess<S> {
     }
 }
 
+//Waits for the child to finish. Returns `None` if the query was interrupted in
+//the meantime.
+fn wait_for(
+    child: &mut Child,
+    interrupt_receiver: &mpsc::Receiver<()>,
+) -> Result<Option<ExitStatus>, String> {
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(Some(status)),
+            Ok(None) => {
+                if interrupt_receiver.try_recv().is_ok() {
+                    kill_process_group(child);
+                    return Ok(None);
+                }
+            }
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+}
+
+//Scripts run in their own process group, so we can stop them including all
+//processes they spawned.
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    //SAFETY: kill has no memory safety requirements. The negative id addresses
+    //the process group of the child.
+    if unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } != 0 {
+        child.kill().expect("Terminating process killed!");
+    }
+    let _ = child.wait();
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) {
+    child.kill().expect("Terminating process killed!");
+    let _ = child.wait();
+}
+
+fn build_envs(build: Option<&BuildStep>) -> Vec<(String, String)> {
+    match build {
+        Some(build) => vec![(
+            String::from("CRS_BUILD_DIR"),
+            build.directory.display().to_string(),
+        )],
+        None => vec![],
+    }
+}
+
+//Marks a build directory whose outputs can be reused.
+fn build_marker(build: &BuildStep) -> PathBuf {
+    build.directory.with_extension("done")
+}
+
+//Returns whether the build succeeded or `None` if it was interrupted.
+fn run_build(
+    build: &BuildStep,
+    location: &str,
+    interrupt_receiver: &mpsc::Receiver<()>,
+    log: &Option<(PathBuf, PathBuf)>,
+) -> Result<Option<bool>, String> {
+    let marker = build_marker(build);
+    if build.reuse && marker.exists() {
+        return Ok(Some(true));
+    }
+
+    let _ = fs::remove_file(&marker);
+    let _ = fs::remove_dir_all(&build.directory);
+    fs::create_dir_all(&build.directory).map_err(|err| {
+        format!(
+            "couldn't create build directory {}: {}",
+            build.directory.display(),
+            err
+        )
+    })?;
+
+    let (log_stdout, log_stderr) = match log {
+        Some((stdout, stderr)) => (Some(stdout.clone()), Some(stderr.clone())),
+        None => (None, None),
+    };
+    let mut child = run_script_async(
+        location,
+        &build.script,
+        &[],
+        log_stdout,
+        log_stderr,
+        &build_envs(Some(build)),
+    )
+    .map_err(|err| format!("{} couldn't be executed: {}", build.script, err))?;
+
+    match wait_for(&mut child, interrupt_receiver)? {
+        Some(status) => {
+            if status.success() && build.reuse {
+                fs::write(&marker, "").map_err(|err| err.to_string())?;
+            }
+            Ok(Some(status.success()))
+        }
+        None => Ok(None),
+    }
+}
+
+fn run_hook(location: &str, script_path: &str) -> Result<(), String> {
+    let mut command = Command::new(script_path);
+
+    match run_command_sync(location, &mut command) {
+        Ok(output) => {
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{} failed ({}): {}",
+                    script_path,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+            }
+        }
+        Err(err) => Err(format!("{} couldn't be executed: {}", script_path, err)),
+    }
+}
+
+//Clean up after an aborted query. The query already failed, so an error of the
+//hook itself is only reported.
+fn run_post_query(hooks: &Hooks, worktree: &Worktree) {
+    if let Some(post_query) = &hooks.post_query {
+        if let Err(message) = run_hook(&worktree.location, post_query) {
+            eprintln!("{}", ProcessError::PostQueryError(message));
+        }
+    }
+}
+
 fn scerror(transmitter: &mpsc::Sender<ProcessResponse>, id: u32, commit: String, message: String) {
     error(transmitter, id, commit, ProcessError::ScriptError(message));
 }
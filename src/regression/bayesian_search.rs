@@ -0,0 +1,399 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use daggy::{NodeIndex, Walker};
+
+use crate::{
+    graph::{valid_closure, Adag},
+    log::{self, Event},
+};
+
+use super::{
+    path_selection::{region, topological_order},
+    AlgorithmResponse, Probability, RegressionAlgorithm, RegressionPoint, TestResult,
+};
+
+/// How reliable the test is and when the search is done.
+#[derive(Debug, Clone, Copy)]
+pub struct BayesianSettings {
+    /// Probability that a good commit is tested bad.
+    pub false_positive_rate: f64,
+    /// Probability that a bad commit is tested good.
+    pub false_negative_rate: f64,
+    /// Probability of the most likely regression point, at which the search
+    /// stops.
+    pub confidence: f64,
+}
+
+//Tests that tell less (in bits) about the regression point aren't worth it.
+const MIN_GAIN: f64 = 1e-6;
+//Running tests of commits that are good or bad with this probability are
+//interrupted.
+const CERTAIN: f64 = 0.999;
+//Only the most likely candidates are reported.
+const REPORTED_CANDIDATES: usize = 10;
+const REPORTED_PROBABILITY: f64 = 0.01;
+
+/// Search for tests that fail or pass by chance. Keeps the probability of each
+/// commit between the sources and the target to be the regression point,
+/// and tests the commits that are expected to tell the most about it. Commits
+/// are tested again, if that's still the most informative test.
+pub struct BayesianSearch {
+    graph: Adag<()>,
+    target: NodeIndex,
+    settings: BayesianSettings,
+    //Commits that can be the regression point, parents before children.
+    candidates: Vec<NodeIndex>,
+    position: HashMap<NodeIndex, usize>,
+    //For each candidate, the positions of the candidates that are its
+    //ancestors (including itself) as bit set. It is bad, if one of them is
+    //the regression point.
+    ancestors: Vec<Vec<u64>>,
+    //Probability of each candidate to be the regression point.
+    posterior: Vec<f64>,
+    untestable: HashSet<NodeIndex>,
+    jobs_await: HashSet<NodeIndex>,
+    interrupts: Vec<String>,
+    log_path: Option<PathBuf>,
+}
+
+impl BayesianSearch {
+    pub fn new(graph: Adag<()>, settings: BayesianSettings, log_path: Option<PathBuf>) -> Self {
+        let sources = graph
+            .sources
+            .iter()
+            .filter_map(|hash| graph.get_index(hash))
+            .collect::<HashSet<_>>();
+        //Searches of more than one target are rejected by `load_core`.
+        let target = graph.index(&graph.targets[0]);
+
+        let valid = valid_closure(&graph, &sources);
+        let candidates =
+            topological_order(&graph, &region(&graph, &HashSet::from([target]), &valid));
+        let position = candidates
+            .iter()
+            .enumerate()
+            .map(|(position, index)| (*index, position))
+            .collect::<HashMap<_, _>>();
+
+        let words = candidates.len().div_ceil(64);
+        let mut ancestors = Vec::<Vec<u64>>::with_capacity(candidates.len());
+        for (current, index) in candidates.iter().enumerate() {
+            let mut bits = vec![0; words];
+            bits[current / 64] |= 1 << (current % 64);
+            for (_, parent) in graph.graph.parents(*index).iter(&graph.graph) {
+                if let Some(parent) = position.get(&parent) {
+                    for (word, parent_word) in bits.iter_mut().zip(&ancestors[*parent]) {
+                        *word |= parent_word;
+                    }
+                }
+            }
+            ancestors.push(bits);
+        }
+
+        eprintln!(
+            "----\nBayesian search initialized\n{} Candidates\n----",
+            candidates.len()
+        );
+
+        let search = BayesianSearch {
+            graph,
+            target,
+            settings,
+            posterior: vec![1.0 / candidates.len() as f64; candidates.len()],
+            candidates,
+            position,
+            ancestors,
+            untestable: HashSet::new(),
+            jobs_await: HashSet::new(),
+            interrupts: vec![],
+            log_path,
+        };
+        search.log_step(None);
+        search
+    }
+
+    fn log_step(&self, commit: Option<String>) {
+        if let Some(log_path) = &self.log_path {
+            log::write_event(
+                log_path,
+                Event::PosteriorUpdated {
+                    commit,
+                    most_likely: self.most_likely(),
+                },
+            );
+
+            if self.done() {
+                for regression in self.results() {
+                    log::write_event(log_path, Event::RegressionFound(&regression));
+                }
+            }
+        }
+    }
+
+    //Candidates with a notable probability, the most likely first.
+    fn most_likely(&self) -> Vec<Probability> {
+        let mut positions = (0..self.candidates.len())
+            .filter(|position| self.posterior[*position] >= REPORTED_PROBABILITY)
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| {
+            self.posterior[*b]
+                .partial_cmp(&self.posterior[*a])
+                .unwrap_or(Ordering::Equal)
+                .then(a.cmp(b))
+        });
+
+        positions
+            .into_iter()
+            .take(REPORTED_CANDIDATES)
+            .map(|position| Probability {
+                commit: self.graph.hash_from_index(self.candidates[position]),
+                probability: self.posterior[position],
+            })
+            .collect()
+    }
+
+    fn best(&self) -> Option<(usize, f64)> {
+        self.posterior
+            .iter()
+            .cloned()
+            .enumerate()
+            .fold(None, |best, (position, probability)| match best {
+                Some((_, p)) if p >= probability => best,
+                _ => Some((position, probability)),
+            })
+    }
+
+    //The candidates, that are ancestors of `index`.
+    fn bad_candidates(&self, index: NodeIndex) -> Vec<u64> {
+        if let Some(position) = self.position.get(&index) {
+            return self.ancestors[*position].clone();
+        }
+
+        //Commits outside of the candidates, e.g. ancestors of other targets.
+        let mut bits = vec![0; self.candidates.len().div_ceil(64)];
+        let mut visited = HashSet::from([index]);
+        let mut queue = vec![index];
+        while let Some(current) = queue.pop() {
+            if let Some(position) = self.position.get(&current) {
+                for (word, ancestor_word) in bits.iter_mut().zip(&self.ancestors[*position]) {
+                    *word |= ancestor_word;
+                }
+                continue;
+            }
+            for (_, parent) in self.graph.graph.parents(current).iter(&self.graph.graph) {
+                if visited.insert(parent) {
+                    queue.push(parent);
+                }
+            }
+        }
+        bits
+    }
+
+    //Expected information (in bits) of a test on a commit, that is bad with
+    //probability `bad`.
+    fn information_gain(&self, bad: f64) -> f64 {
+        let BayesianSettings {
+            false_positive_rate: fp,
+            false_negative_rate: fneg,
+            ..
+        } = self.settings;
+        let tested_bad = bad * (1.0 - fneg) + (1.0 - bad) * fp;
+        entropy(tested_bad) - bad * entropy(fneg) - (1.0 - bad) * entropy(fp)
+    }
+
+    //Probability that a commit is bad.
+    fn bad_probability(&self, index: NodeIndex) -> f64 {
+        let mut bad = 0.0;
+        for_each_set(&self.bad_candidates(index), |position| {
+            bad += self.posterior[position];
+        });
+        bad.min(1.0)
+    }
+
+    //Running tests of commits whose state is certain by now tell next to
+    //nothing anymore.
+    fn interrupt_certain(&mut self) {
+        let certain = self
+            .jobs_await
+            .iter()
+            .filter(|index| {
+                let bad = self.bad_probability(**index);
+                bad.max(1.0 - bad) >= CERTAIN
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for index in certain {
+            self.jobs_await.remove(&index);
+            self.interrupts.push(self.graph.hash_from_index(index));
+        }
+    }
+
+    //The commit with the highest information gain. Running tests will tell
+    //apart the candidates by whether they are ancestors of the tested
+    //commits, so only the information within these groups counts.
+    fn next_commit(&self) -> Option<NodeIndex> {
+        let pending = self
+            .jobs_await
+            .iter()
+            .map(|index| self.bad_candidates(*index))
+            .collect::<Vec<_>>();
+        let mut group_ids = HashMap::<Vec<bool>, usize>::new();
+        let groups = (0..self.candidates.len())
+            .map(|position| {
+                let key = pending.iter().map(|bits| is_set(bits, position)).collect();
+                let id = group_ids.len();
+                *group_ids.entry(key).or_insert(id)
+            })
+            .collect::<Vec<_>>();
+
+        let mut mass = vec![0.0; group_ids.len()];
+        for (position, group) in groups.iter().enumerate() {
+            mass[*group] += self.posterior[position];
+        }
+
+        let mut best: Option<(NodeIndex, f64)> = None;
+        let mut bad_mass = vec![0.0; group_ids.len()];
+        for (position, candidate) in self.candidates.iter().enumerate() {
+            if self.untestable.contains(candidate) || self.jobs_await.contains(candidate) {
+                continue;
+            }
+
+            bad_mass.iter_mut().for_each(|m| *m = 0.0);
+            for_each_set(&self.ancestors[position], |ancestor| {
+                bad_mass[groups[ancestor]] += self.posterior[ancestor];
+            });
+            let gain = mass
+                .iter()
+                .zip(&bad_mass)
+                .filter(|(mass, _)| **mass > 0.0)
+                .map(|(mass, bad)| mass * self.information_gain((bad / mass).min(1.0)))
+                .sum::<f64>();
+
+            match best {
+                Some((_, best_gain)) if best_gain >= gain => {}
+                _ => best = Some((*candidate, gain)),
+            }
+        }
+
+        best.filter(|(_, gain)| *gain > MIN_GAIN)
+            .map(|(index, _)| index)
+    }
+}
+
+fn entropy(p: f64) -> f64 {
+    if p <= 0.0 || p >= 1.0 {
+        0.0
+    } else {
+        -p * p.log2() - (1.0 - p) * (1.0 - p).log2()
+    }
+}
+
+fn is_set(bits: &[u64], position: usize) -> bool {
+    bits[position / 64] & (1 << (position % 64)) != 0
+}
+
+fn for_each_set(bits: &[u64], mut f: impl FnMut(usize)) {
+    for (index, word) in bits.iter().enumerate() {
+        let mut word = *word;
+        while word != 0 {
+            f(index * 64 + word.trailing_zeros() as usize);
+            word &= word - 1;
+        }
+    }
+}
+
+impl RegressionAlgorithm for BayesianSearch {
+    fn add_result(&mut self, commit: String, result: TestResult) {
+        let index = self.graph.index(&commit);
+        self.jobs_await.remove(&index);
+
+        if result == TestResult::Ignore {
+            self.untestable.insert(index);
+            self.log_step(Some(commit));
+            return;
+        }
+
+        let BayesianSettings {
+            false_positive_rate: fp,
+            false_negative_rate: fneg,
+            ..
+        } = self.settings;
+        let likelihood = |is_bad: bool| match (&result, is_bad) {
+            (TestResult::False, true) => 1.0 - fneg,
+            (TestResult::False, false) => fp,
+            (_, true) => fneg,
+            (_, false) => 1.0 - fp,
+        };
+
+        let bad = self.bad_candidates(index);
+        let posterior = self
+            .posterior
+            .iter()
+            .enumerate()
+            .map(|(position, p)| p * likelihood(is_set(&bad, position)))
+            .collect::<Vec<_>>();
+        let total = posterior.iter().sum::<f64>();
+        if total > 0.0 {
+            self.posterior = posterior.into_iter().map(|p| p / total).collect();
+        } else {
+            eprintln!(
+                "The result of {} contradicts all earlier results and is ignored!",
+                commit
+            );
+        }
+
+        if let Some((position, probability)) = self.best() {
+            eprintln!(
+                "Most likely regression point: {} ({:.3})",
+                self.graph.hash_from_index(self.candidates[position]),
+                probability
+            );
+        }
+        self.interrupt_certain();
+        self.log_step(Some(commit));
+    }
+
+    fn next_job(&mut self, _capacity: u32, _expected_capacity: u32) -> AlgorithmResponse<'_> {
+        match self.next_commit() {
+            Some(index) => {
+                self.jobs_await.insert(index);
+                AlgorithmResponse::Job(self.graph.hash_from_index(index))
+            }
+            None if !self.jobs_await.is_empty() => AlgorithmResponse::WaitForResult,
+            None => AlgorithmResponse::InternalError(
+                "Bayesian search: No test can make the result more certain!",
+            ),
+        }
+    }
+
+    fn interrupts(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.interrupts)
+    }
+
+    fn done(&self) -> bool {
+        match self.best() {
+            Some((_, probability)) => probability >= self.settings.confidence,
+            None => true,
+        }
+    }
+
+    fn results(&self) -> Vec<RegressionPoint> {
+        match self.best() {
+            Some((position, _)) => vec![RegressionPoint {
+                target: self.graph.hash_from_index(self.target),
+                regression_point: self.graph.hash_from_index(self.candidates[position]),
+                candidates: vec![],
+                probabilities: self.most_likely(),
+            }],
+            None => vec![],
+        }
+    }
+
+    fn repeats_tests(&self) -> bool {
+        true
+    }
+}
@@ -1,14 +1,14 @@
 use std::collections::VecDeque;
 
-use super::{PathAlgorithm, RegressionAlgorithm, RegressionPoint, TestResult, interval_search::IntervalSearch};
+use super::{PathAlgorithm, RegressionAlgorithm, RegressionPoint, TestResult, interval_search::{IntervalSearch, Scheduling}};
 
 pub struct BinarySearch {
     search: IntervalSearch
 }
 
 impl PathAlgorithm for BinarySearch {
-    fn new(path: VecDeque<String>) -> Self {
-        BinarySearch { search: IntervalSearch::new(path) }
+    fn new(path: VecDeque<String>, scheduling: Scheduling) -> Self {
+        BinarySearch { search: IntervalSearch::new(path, scheduling) }
     }
 }
 
@@ -17,7 +17,7 @@ impl RegressionAlgorithm for BinarySearch {
         self.search.add_result(commit, result)
     }
 
-    fn next_job(&mut self, _: u32 , expected_capacity: u32) -> super::AlgorithmResponse {
+    fn next_job(&mut self, _: u32 , expected_capacity: u32) -> super::AlgorithmResponse<'_> {
         self.search.next_job(expected_capacity as usize, take_uniform_sample)
     }
 
@@ -34,7 +34,7 @@ impl RegressionAlgorithm for BinarySearch {
     }
 }
 
-fn take_uniform_sample<S: Clone + Eq>(
+pub fn take_uniform_sample<S: Clone + Eq>(
     path: &VecDeque<S>,
     left: &S,
     right: &S,
@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+
+use super::{
+    binary_search::take_uniform_sample,
+    interval_search::{IntervalSearch, Scheduling},
+    PathAlgorithm, RegressionAlgorithm, RegressionPoint, TestResult,
+};
+
+/// Probes at distances 1, 2, 4, 8, ... back from the target, until a valid
+/// commit brackets the regression point. Then it continues as binary search
+/// within the bracket. Fast for regressions close to the target.
+pub struct GallopingSearch {
+    search: IntervalSearch,
+}
+
+/// Mirrored `GallopingSearch`, that gallops forward from the source. Fast for
+/// regressions close to the source.
+pub struct SourceGallopingSearch {
+    search: IntervalSearch,
+}
+
+impl PathAlgorithm for GallopingSearch {
+    fn new(path: VecDeque<String>, scheduling: Scheduling) -> Self {
+        GallopingSearch {
+            search: IntervalSearch::new(path, scheduling),
+        }
+    }
+}
+
+impl PathAlgorithm for SourceGallopingSearch {
+    fn new(path: VecDeque<String>, scheduling: Scheduling) -> Self {
+        SourceGallopingSearch {
+            search: IntervalSearch::new(path, scheduling),
+        }
+    }
+}
+
+impl RegressionAlgorithm for GallopingSearch {
+    fn add_result(&mut self, commit: String, result: TestResult) {
+        self.search.add_result(commit, result)
+    }
+
+    fn next_job(&mut self, _: u32, expected_capacity: u32) -> super::AlgorithmResponse<'_> {
+        self.search
+            .next_job(expected_capacity as usize, gallop_from_target)
+    }
+
+    fn interrupts(&mut self) -> Vec<String> {
+        self.search.interrupts()
+    }
+
+    fn done(&self) -> bool {
+        self.search.done()
+    }
+
+    fn results(&self) -> Vec<RegressionPoint> {
+        self.search.results()
+    }
+}
+
+impl RegressionAlgorithm for SourceGallopingSearch {
+    fn add_result(&mut self, commit: String, result: TestResult) {
+        self.search.add_result(commit, result)
+    }
+
+    fn next_job(&mut self, _: u32, expected_capacity: u32) -> super::AlgorithmResponse<'_> {
+        self.search
+            .next_job(expected_capacity as usize, gallop_from_source)
+    }
+
+    fn interrupts(&mut self) -> Vec<String> {
+        self.search.interrupts()
+    }
+
+    fn done(&self) -> bool {
+        self.search.done()
+    }
+
+    fn results(&self) -> Vec<RegressionPoint> {
+        self.search.results()
+    }
+}
+
+//As long as no commit but the source is known to be valid, the regression
+//point isn't bracketed yet. The next probes are at the powers of two that are
+//farther from the target than `right`, so each batch continues the doubling of
+//the last one.
+fn gallop_from_target(
+    path: &VecDeque<String>,
+    left: &String,
+    right: &String,
+    sample_size: usize,
+) -> Result<VecDeque<String>, ()> {
+    let (l, r) = bounds(path, left, right)?;
+    let last = path.len() - 1;
+
+    let mut samples = VecDeque::new();
+    if l == 0 {
+        let mut distance = 1;
+        while distance <= last - r {
+            distance *= 2;
+        }
+        while samples.len() < sample_size && distance < last - l {
+            samples.push_front(path[last - distance].clone());
+            distance *= 2;
+        }
+    }
+
+    if samples.is_empty() {
+        take_uniform_sample(path, left, right, sample_size)
+    } else {
+        Ok(samples)
+    }
+}
+
+//Same as `gallop_from_target`, but the distances are counted from the source
+//and the regression point is bracketed by the first invalid commit.
+fn gallop_from_source(
+    path: &VecDeque<String>,
+    left: &String,
+    right: &String,
+    sample_size: usize,
+) -> Result<VecDeque<String>, ()> {
+    let (l, r) = bounds(path, left, right)?;
+    let last = path.len() - 1;
+
+    let mut samples = VecDeque::new();
+    if r == last {
+        let mut distance = 1;
+        while distance <= l {
+            distance *= 2;
+        }
+        while samples.len() < sample_size && distance < r {
+            samples.push_back(path[distance].clone());
+            distance *= 2;
+        }
+    }
+
+    if samples.is_empty() {
+        take_uniform_sample(path, left, right, sample_size)
+    } else {
+        Ok(samples)
+    }
+}
+
+fn bounds(path: &VecDeque<String>, left: &String, right: &String) -> Result<(usize, usize), ()> {
+    let l = path.iter().position(|hash| hash == left).ok_or(())?;
+    let r = path.iter().position(|hash| hash == right).ok_or(())?;
+    Ok((l.min(r), l.max(r)))
+}
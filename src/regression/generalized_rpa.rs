@@ -1,24 +1,26 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     marker::PhantomData,
 };
 
-use crate::log;
+use crate::log::{self, Event};
 use daggy::{NodeIndex, Walker};
 use priority_queue::PriorityQueue;
 
-use crate::graph::Adag;
+use crate::graph::{narrow_interval, skipped_ancestors, Adag};
 
 use super::{
     path_selection::PathSelection,
     rpa_extension::ExtendedSearch,
-    rpa_util::{RPANode, Settings},
-    AlgorithmResponse, PathAlgorithm, RegressionAlgorithm, RegressionPoint, TestResult,
+    rpa_util::{Commits, Settings},
+    AlgorithmResponse, Interval, PathAlgorithm, RegressionAlgorithm, RegressionPoint, TestResult,
 };
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct RPA<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> {
-    commits: Adag<RPANode, E>,
+    commits: Commits<E>,
     ordering: PriorityQueue<(NodeIndex, NodeIndex), i32>,
+    distances: P::Distances,
     remaining_targets: HashSet<NodeIndex>,
     valid_nodes: HashSet<NodeIndex>,
     current_search: Option<S>,
@@ -28,6 +30,7 @@ pub struct RPA<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clon
     interrupts: Vec<String>,
     log_path: Option<std::path::PathBuf>,
     counter: usize,
+    path: Option<Vec<String>>,
     _marker: PhantomData<P>,
 }
 
@@ -35,7 +38,7 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone + std::f
     RPA<P, S, E>
 {
     pub fn new(
-        input_graph: Adag<String, E>,
+        input_graph: Adag<E>,
         settings: Settings,
         log_path: Option<std::path::PathBuf>,
     ) -> Self {
@@ -43,16 +46,14 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone + std::f
             input_graph
                 .targets
                 .iter()
-                .filter_map(|hash| input_graph.indexation.get(hash))
-                .copied(),
+                .filter_map(|hash| input_graph.get_index(hash)),
         );
 
         let sources_index = HashSet::from_iter(
             input_graph
                 .sources
                 .iter()
-                .filter_map(|hash| input_graph.indexation.get(hash))
-                .copied(),
+                .filter_map(|hash| input_graph.get_index(hash)),
         );
 
         let annotated = annotate_graph(input_graph);
@@ -62,63 +63,46 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone + std::f
 RPA initialized
 {} Commits
 ----",
-            annotated.graph.node_count()
+            annotated.graph.graph.node_count()
         );
 
-        let ordering = P::calculate_distances(&annotated, &targets_index, &sources_index);
-        let exrpa_log_dir = log_path.map(|p| log::add_dir("exrpa", &p));
-        if let Some(log_dir) = &exrpa_log_dir {
-            log::create_file("summary", log_dir);
-        }
+        let mut distances = P::Distances::default();
+        let ordering =
+            P::calculate_distances(&annotated, &targets_index, &sources_index, &mut distances);
 
         RPA {
             commits: annotated,
             remaining_targets: targets_index,
             valid_nodes: sources_index,
             ordering,
+            distances,
             current_search: None,
             extended_search: None,
             regressions: vec![],
             interrupts: vec![],
             settings,
-            log_path: exrpa_log_dir,
+            log_path,
             counter: 0,
+            path: None,
             _marker: PhantomData,
         }
     }
 }
 
-fn annotate_graph<E: Clone>(dvcs: Adag<String, E>) -> Adag<RPANode, E> {
-    let mapped = dvcs.graph.map(
-        |_, hash| {
-            let is_source = dvcs.sources.contains(hash);
-            let is_target = dvcs.targets.contains(hash);
-
-            if is_source && is_target {
-                panic!("{} is a source as well as a target!", hash);
-            }
-
-            let result = if is_source {
-                Some(TestResult::True)
-            } else if is_target {
-                Some(TestResult::False)
-            } else {
-                None
-            };
+fn annotate_graph<E>(dvcs: Adag<E>) -> Commits<E> {
+    let mut results = HashMap::new();
+    for source in &dvcs.sources {
+        results.insert(dvcs.index(source), TestResult::True);
+    }
+    for target in &dvcs.targets {
+        if results.insert(dvcs.index(target), TestResult::False).is_some() {
+            panic!("{} is a source as well as a target!", target);
+        }
+    }
 
-            RPANode {
-                hash: hash.to_string(),
-                result,
-            }
-        },
-        |_, edge| edge.clone(),
-    );
-
-    Adag {
-        sources: dvcs.sources,
-        targets: dvcs.targets,
-        graph: mapped,
-        indexation: dvcs.indexation,
+    Commits {
+        graph: dvcs,
+        results,
     }
 }
 
@@ -126,18 +110,12 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
     for RPA<P, S, E>
 {
     fn add_result(&mut self, commit_hash: String, result: TestResult) {
-        let index = self.commits.index(&commit_hash);
-
-        let node = self
-            .commits
-            .graph
-            .node_weight_mut(index)
-            .expect("Invalid index!");
+        let index = self.commits.graph.index(&commit_hash);
 
         if result == TestResult::True {
             self.valid_nodes.insert(index);
         }
-        node.result = Some(result.clone());
+        self.commits.results.insert(index, result.clone());
 
         let mut reg_point = None;
         if let Some((_, ex_search)) = self.extended_search.as_mut() {
@@ -159,6 +137,7 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
                             &self.valid_nodes,
                             self.log_path.clone(),
                             self.counter,
+                            self.settings.scheduling,
                         ),
                     ));
                 } else {
@@ -186,6 +165,7 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
                             &self.valid_nodes,
                             self.log_path.clone(),
                             self.counter,
+                            self.settings.scheduling,
                         ),
                     ));
                 }
@@ -196,24 +176,31 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
 
         if let Some(reg) = reg_point {
             if self.settings.propagate {
-                self.propagate_results(self.commits.index(&reg.regression_point));
+                self.propagate_results(self.commits.graph.index(&reg.regression_point));
             } else {
                 self.remaining_targets
-                    .remove(&self.commits.index(&reg.target));
-                self.regressions.push(reg);
+                    .remove(&self.commits.graph.index(&reg.target));
+                let candidates = self.candidates(self.commits.graph.index(&reg.regression_point));
+                self.add_regression(RegressionPoint { candidates, ..reg });
             }
         }
 
         if result == TestResult::True {
-            self.ordering =
-                P::calculate_distances(&self.commits, &self.remaining_targets, &self.valid_nodes);
+            P::update_distances(
+                &self.commits,
+                &self.remaining_targets,
+                &self.valid_nodes,
+                &mut self.distances,
+                &mut self.ordering,
+                index,
+            );
         }
     }
 
-    fn next_job(&mut self, capacity: u32, expected_capacity: u32) -> super::AlgorithmResponse {
+    fn next_job(&mut self, capacity: u32, expected_capacity: u32) -> super::AlgorithmResponse<'_> {
         //If there is no active search right now, we have to pick a new path and
-        //start another search.
-        if self.current_search.is_none() && self.extended_search.is_none() {
+        //start another search. Known results can already finish it.
+        while self.current_search.is_none() && self.extended_search.is_none() && !self.done() {
             self.counter += 1;
             let mut path_indices = None;
 
@@ -228,36 +215,32 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
 
             let (start, end) = path_indices.expect("No relevant path was found!");
 
-            let path = P::extract_path(&self.commits, start, end)
+            let path_indices = P::extract_path(&self.commits, &self.distances, start, end);
+            let known = self.known_results(&path_indices);
+            let path = path_indices
                 .iter()
-                .map(|index| {
-                    self.commits
-                        .graph
-                        .node_weight(*index)
-                        .unwrap()
-                        .hash
-                        .to_string()
-                })
+                .map(|index| self.commits.graph.hash_from_index(*index))
                 .collect::<VecDeque<String>>();
 
             let len = path.len();
-            let source_hash = &self.commits.graph.node_weight(start).unwrap().hash;
-            let target_hash = &self.commits.graph.node_weight(end).unwrap().hash;
+            let source_hash = self.commits.graph.hash_from_index(start);
+            let target_hash = self.commits.graph.hash_from_index(end);
 
             if let Some(log_path) = &self.log_path {
-                log::write_to_file(
-                    &format!(
-                        "Path Search ({}): From {} to {}. Path Length: {}\n",
-                        self.counter, source_hash, target_hash, len
-                    ),
-                    &main_log_file(log_path),
+                log::write_event(
+                    log_path,
+                    Event::PathPicked {
+                        search: self.counter,
+                        source: source_hash.clone(),
+                        target: target_hash.clone(),
+                        length: len,
+                        path: Vec::from(path.clone()),
+                    },
                 );
-                let path_file = log::create_file(&format!("{}_path", self.counter), log_path);
-                let path_string = path.clone().make_contiguous().join("\n");
-                log::write_to_file(&path_string, &path_file);
             }
 
-            let search = S::new(path);
+            self.path = Some(Vec::from(path.clone()));
+            let search = S::new(path, self.settings.scheduling);
             eprintln!(
                 "RPA - Algorithm:
 picked new path
@@ -268,12 +251,18 @@ length: {}
             );
 
             self.current_search = Some(search);
+            for (hash, result) in known {
+                self.add_result(hash, result);
+            }
         }
 
+        let done = self.done();
         if let Some(search) = self.current_search.as_mut() {
             search.next_job(capacity, expected_capacity)
         } else if let Some((_, ex_search)) = self.extended_search.as_mut() {
             ex_search.next_job(capacity, expected_capacity)
+        } else if done {
+            AlgorithmResponse::WaitForResult
         } else {
             AlgorithmResponse::InternalError("No active search!")
         }
@@ -292,6 +281,29 @@ length: {}
     fn results(&self) -> Vec<RegressionPoint> {
         self.regressions.clone()
     }
+
+    fn current_path(&self) -> Option<Vec<String>> {
+        self.path.clone()
+    }
+
+    fn intervals(&self) -> Vec<Interval> {
+        let hash = |index: NodeIndex| self.commits.graph.hash_from_index(index);
+        let is_invalid =
+            |index: NodeIndex| self.commits.result(index) == Some(&TestResult::False);
+
+        self.remaining_targets
+            .iter()
+            .map(|target| {
+                let (last_good, first_bad) =
+                    narrow_interval(&self.commits.graph, &self.valid_nodes, is_invalid, *target);
+                Interval {
+                    target: hash(*target),
+                    last_good: last_good.into_iter().map(hash).collect(),
+                    first_bad: hash(first_bad),
+                }
+            })
+            .collect()
+    }
 }
 
 impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> RPA<P, S, E> {
@@ -299,7 +311,8 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> RPA<P,
         let mut queue = VecDeque::<NodeIndex>::new();
         let mut visited = HashSet::new();
 
-        let regression_hash = self.commits.node_from_index(regression).hash;
+        let regression_hash = self.commits.graph.hash_from_index(regression);
+        let candidates = self.candidates(regression);
 
         queue.push_back(regression);
         visited.insert(regression);
@@ -309,18 +322,17 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> RPA<P,
 
             if self.remaining_targets.contains(&current) {
                 self.remaining_targets.remove(&current);
-                let target_hash = self.node_from_index_unchecked(&current).hash.to_string();
-                self.regressions.push(RegressionPoint {
+                let target_hash = self.commits.graph.hash_from_index(current);
+                self.add_regression(RegressionPoint {
                     target: target_hash,
                     regression_point: regression_hash.to_string(),
+                    candidates: candidates.clone(),
+                    probabilities: vec![],
                 });
             }
 
-            for (_, next) in self
-                .commits
-                .graph
-                .children(current)
-                .iter(&self.commits.graph)
+            let graph = &self.commits.graph.graph;
+            for (_, next) in graph.children(current).iter(graph)
             {
                 if !visited.contains(&next) {
                     queue.push_back(next);
@@ -330,14 +342,58 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> RPA<P,
         }
     }
 
-    fn node_from_index_unchecked(&mut self, index: &NodeIndex) -> &RPANode {
-        self.commits
-            .graph
-            .node_weight(*index)
-            .expect("node_from_index_unchecked() failed!")
+    //Results on the path that are known before its search, e.g. of suspects.
+    //Only the first invalid commit and the last valid commit before it narrow
+    //the search.
+    fn known_results(&self, path: &VecDeque<NodeIndex>) -> Vec<(String, TestResult)> {
+        let mut last_valid = None;
+        let mut first_invalid = None;
+        for index in path.iter().skip(1).take(path.len().saturating_sub(2)) {
+            match self.commits.result(*index) {
+                Some(TestResult::True) => last_valid = Some(*index),
+                Some(TestResult::False) => {
+                    first_invalid = Some(*index);
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        [last_valid, first_invalid]
+            .into_iter()
+            .flatten()
+            .map(|index| {
+                (
+                    self.commits.graph.hash_from_index(index),
+                    self.commits.result(index).unwrap().clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn add_regression(&mut self, regression: RegressionPoint) {
+        if let Some(log_path) = &self.log_path {
+            log::write_event(log_path, Event::RegressionFound(&regression));
+        }
+        self.regressions.push(regression);
+    }
+
+    fn candidates(&self, regression: NodeIndex) -> Vec<String> {
+        let is_skipped =
+            |index: NodeIndex| self.commits.result(index) == Some(&TestResult::Ignore);
+        let skipped =
+            skipped_ancestors(&self.commits.graph, &self.valid_nodes, is_skipped, regression);
+
+        if skipped.is_empty() {
+            vec![]
+        } else {
+            skipped
+                .into_iter()
+                .rev()
+                .chain([regression])
+                .map(|index| self.commits.graph.hash_from_index(index))
+                .collect()
+        }
     }
-}
 
-pub fn main_log_file(path: &std::path::Path) -> std::path::PathBuf {
-    path.join("summary")
 }
@@ -2,14 +2,17 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 use daggy::{NodeIndex, Walker};
 
-use crate::{graph::Adag, log};
+use crate::{
+    graph::{narrow_interval, skipped_ancestors, Adag},
+    log::{self, Event},
+};
 
 use self::bisection_tree::*;
 
-use super::{RegressionAlgorithm, RegressionPoint, TestResult};
+use super::{Interval, RegressionAlgorithm, RegressionPoint, TestResult};
 
 pub struct GitBisect {
-    graph: Adag<String, ()>,
+    graph: Adag<()>,
     results: HashMap<NodeIndex, TestResult>,
     valid_nodes: HashSet<NodeIndex>,
     ignored_nodes: HashSet<NodeIndex>,
@@ -23,13 +26,12 @@ pub struct GitBisect {
 }
 
 impl GitBisect {
-    pub fn new(graph: Adag<String, ()>, log_path: Option<std::path::PathBuf>) -> Self {
+    pub fn new(graph: Adag<()>, log_path: Option<std::path::PathBuf>) -> Self {
         let mut sources_index = HashSet::from_iter(
             graph
                 .sources
                 .iter()
-                .filter_map(|hash| graph.indexation.get(hash))
-                .copied(),
+                .filter_map(|hash| graph.get_index(hash)),
         );
 
         let mut target_index = graph.index(&graph.targets[0]);
@@ -48,20 +50,7 @@ impl GitBisect {
             graph.graph.node_count()
         );
 
-        let bisect_log_dir = log_path.map(|p| log::add_dir("bisect", &p));
-        if let Some(log_dir) = &bisect_log_dir {
-            log::create_file("summary", log_dir);
-            log::write_to_file(
-                &format!(
-                    "Init Bisect:\nSpeculation Tree:\n{}\n---\n\n",
-                    tree.display(&graph, &results)
-                ),
-                &summary_log_file(log_dir),
-            )
-
-        }
-
-        GitBisect {
+        let bisect = GitBisect {
             graph,
             valid_nodes: sources_index,
             ignored_nodes,
@@ -72,7 +61,36 @@ impl GitBisect {
             jobs: VecDeque::new(),
             interrupts: HashSet::new(),
             current_target: target_index,
-            log_path: bisect_log_dir,
+            log_path,
+        };
+        bisect.log_step("init", None);
+        bisect
+    }
+
+    fn log_step(&self, action: &'static str, commit: Option<String>) {
+        if let Some(log_path) = &self.log_path {
+            log::write_event(
+                log_path,
+                Event::BisectStep {
+                    action,
+                    commit,
+                    in_progress: self
+                        .jobs_await
+                        .iter()
+                        .map(|i| self.graph.hash_from_index(*i))
+                        .collect(),
+                    interrupting: self.interrupts.iter().cloned().collect(),
+                    tree: self
+                        .bisection_tree
+                        .display(&self.graph, &self.results),
+                },
+            );
+
+            if self.done() {
+                for regression in self.results() {
+                    log::write_event(log_path, Event::RegressionFound(&regression));
+                }
+            }
         }
     }
 
@@ -211,7 +229,7 @@ impl GitBisect {
 }
 
 fn new_root(
-    graph: &Adag<String, ()>,
+    graph: &Adag<()>,
     results: &HashMap<NodeIndex, TestResult>,
     valid_nodes: &mut HashSet<NodeIndex>,
     ignored_nodes: &mut HashSet<NodeIndex>,
@@ -279,13 +297,34 @@ impl RegressionAlgorithm for GitBisect {
 
         self.bisection_tree = current;
 
+        //Results of other commits, e.g. known before the search, start a new
+        //tree, as long as no job of the current one is running.
+        if !changed && self.jobs_await.is_empty() {
+            let index = self.graph.index(&commit);
+            let (remaining_nodes, _) =
+                get_subgraph(&self.graph, &self.valid_nodes, self.current_target);
+            if remaining_nodes.contains(&index) {
+                match result {
+                    TestResult::True => {
+                        self.valid_nodes.insert(index);
+                    }
+                    TestResult::False => self.current_target = index,
+                    TestResult::Ignore => {
+                        self.ignored_nodes.insert(index);
+                    }
+                }
+                self.jobs.clear();
+                self.bisection_tree = Child::Unknown;
+            }
+        }
+
         if changed {
             let (remaining_nodes, _) =
                 get_subgraph(&self.graph, &self.valid_nodes, self.current_target);
             self.interrupts.extend(
                 self.jobs_await
                     .difference(&remaining_nodes)
-                    .map(|i| self.graph.node_from_index(*i)),
+                    .map(|i| self.graph.hash_from_index(*i)),
             );
         }
 
@@ -299,34 +338,18 @@ impl RegressionAlgorithm for GitBisect {
             );
         }
 
-        //Logging
-        if let Some(log_path) = &self.log_path {
-            let in_progress = self
-                .jobs_await
-                .iter()
-                .map(|i| self.graph.node_from_index(*i))
-                .collect::<Vec<_>>();
-            let interrupting = self.interrupts.clone();
-            log::write_to_file(
-                &format!(
-                    "Add Result: {} {}\nIn progress: {:?}\nInterrupting: {:?}\nSpeculation Tree:\n{}\n---\n\n",
-                    commit, result, in_progress, interrupting, self.bisection_tree.display(&self.graph, &self.results)
-                ),
-                &summary_log_file(log_path),
-            )
-        }
-        //
+        self.log_step("result", Some(commit));
     }
 
-    fn next_job(&mut self, capacity: u32, _expected_capacity: u32) -> super::AlgorithmResponse {
+    fn next_job(&mut self, capacity: u32, _expected_capacity: u32) -> super::AlgorithmResponse<'_> {
         if self.jobs.is_empty() {
             //First look if there are any jobs left in the tree.
-            let mut jobs = VecDeque::from_iter(self.extract_jobs().into_iter());
+            let mut jobs = VecDeque::from_iter(self.extract_jobs());
 
             //Otherwise try to extend the tree and collect the new jobs
             while jobs.is_empty() {
                 let changed = self.extend_speculation_tree();
-                jobs = VecDeque::from_iter(self.extract_jobs().into_iter());
+                jobs = VecDeque::from_iter(self.extract_jobs());
 
                 if !changed {
                     break;
@@ -340,26 +363,9 @@ impl RegressionAlgorithm for GitBisect {
 
         match (self.jobs.pop_front(), self.jobs_await.is_empty()) {
             (Some(job), _) => {
-                //Logging
-                if let Some(log_path) = &self.log_path {
-                    let in_progress = self
-                        .jobs_await
-                        .iter()
-                        .map(|i| self.graph.node_from_index(*i))
-                        .collect::<Vec<_>>();
-                    let interrupting = self.interrupts.clone();
-                    log::write_to_file(
-                        &format!(
-                            "New Job: {} for capacity {}\nIn progress: {:?}\nInterrupting: {:?}\nSpeculation Tree:\n{}\n---\n\n",
-                            self.graph.node_from_index(job), capacity, in_progress, interrupting, self.bisection_tree.display(&self.graph, &self.results)
-                        ),
-                        &summary_log_file(log_path),
-                    )
-                }
-                //
-
                 self.jobs_await.insert(job);
-                let hash = self.graph.node_from_index(job);
+                let hash = self.graph.hash_from_index(job);
+                self.log_step("job", Some(hash.clone()));
                 super::AlgorithmResponse::Job(hash)
             }
             (None, false) => super::AlgorithmResponse::WaitForResult,
@@ -369,7 +375,7 @@ impl RegressionAlgorithm for GitBisect {
 
     fn interrupts(&mut self) -> Vec<String> {
         let res = std::mem::take(&mut self.interrupts);
-        Vec::from_iter(res.into_iter())
+        Vec::from_iter(res)
     }
 
     fn done(&self) -> bool {
@@ -377,19 +383,58 @@ impl RegressionAlgorithm for GitBisect {
     }
 
     fn results(&self) -> Vec<super::RegressionPoint> {
+        let is_skipped = |index| self.results.get(&index) == Some(&TestResult::Ignore);
+        let skipped = skipped_ancestors(
+            &self.graph,
+            &self.valid_nodes,
+            is_skipped,
+            self.current_target,
+        );
+        let candidates = if skipped.is_empty() {
+            vec![]
+        } else {
+            skipped
+                .into_iter()
+                .rev()
+                .chain([self.current_target])
+                .map(|index| self.graph.hash_from_index(index))
+                .collect()
+        };
+
         vec![RegressionPoint {
-            target: self.graph.node_from_index(self.original_target),
-            regression_point: self.graph.node_from_index(self.current_target),
+            target: self.graph.hash_from_index(self.original_target),
+            regression_point: self.graph.hash_from_index(self.current_target),
+            candidates,
+            probabilities: vec![],
         }]
     }
-}
 
-pub fn summary_log_file(path: &std::path::Path) -> std::path::PathBuf {
-    path.join("summary")
+    fn intervals(&self) -> Vec<Interval> {
+        if self.done() {
+            return vec![];
+        }
+
+        let is_invalid = |index| self.results.get(&index) == Some(&TestResult::False);
+        let (last_good, first_bad) = narrow_interval(
+            &self.graph,
+            &self.valid_nodes,
+            is_invalid,
+            self.original_target,
+        );
+
+        vec![Interval {
+            target: self.graph.hash_from_index(self.original_target),
+            last_good: last_good
+                .into_iter()
+                .map(|index| self.graph.hash_from_index(index))
+                .collect(),
+            first_bad: self.graph.hash_from_index(first_bad),
+        }]
+    }
 }
 
 pub fn associated_value_bisection(
-    graph: &Adag<String, ()>,
+    graph: &Adag<()>,
     sources: &HashSet<NodeIndex>,
     ignored: &HashSet<NodeIndex>,
     target: NodeIndex,
@@ -463,7 +508,9 @@ pub fn associated_value_bisection(
             break;
         }
 
-        if anc > size / 2 {
+        //Descendants can't be better bisection points, unless this node is
+        //skipped.
+        if anc > size / 2 && !ignored.contains(&current_index) {
             continue;
         }
 
@@ -482,7 +529,7 @@ pub fn associated_value_bisection(
                 match sections_of_nodes.get_mut(&child_index) {
                     Some((child_section, child_offset)) => {
                         *child_offset += offset;
-                        child_section.extend(sections.into_iter());
+                        child_section.extend(sections);
                     }
                     None => {
                         sections_of_nodes.insert(child_index, (sections, offset + 1));
@@ -516,18 +563,19 @@ pub fn associated_value_bisection(
         }
     }
 
+    //If there are only skipped nodes left, the search can't be narrowed down
+    //any further.
     let (bisection_point, _associated_value) = number_of_ancestors
         .into_iter()
-        .filter(|(n, _)| !ignored.contains(n))
+        .filter(|(n, _)| !ignored.contains(n) && *n != target)
         .map(|(n, v)| (n, std::cmp::min(v, size - v)))
-        .max_by(|(_, v1), (_, v2)| v1.cmp(v2))
-        .unwrap();
+        .max_by(|(_, v1), (_, v2)| v1.cmp(v2))?;
 
     Some(bisection_point)
 }
 
 fn get_subgraph(
-    graph: &Adag<String, ()>,
+    graph: &Adag<()>,
     sources: &HashSet<NodeIndex>,
     target: NodeIndex,
 ) -> (HashSet<NodeIndex>, HashSet<NodeIndex>) {
@@ -653,7 +701,7 @@ mod bisection_tree {
     }
 
     impl BisectionTree {
-        pub(crate) fn display(&self, context: &Adag<String, ()>, results: &HashMap<NodeIndex, TestResult>) -> String {
+        pub(crate) fn display(&self, context: &Adag<()>, results: &HashMap<NodeIndex, TestResult>) -> String {
             let mut out = String::from("");
             let mut stack = Vec::<_>::new();
             stack.push((self, 0, None));
@@ -670,7 +718,7 @@ mod bisection_tree {
                     Child::Next(n) => {
                         stack.push((&n.left, level + 1, Some(false)));
                         stack.push((&n.right, level + 1, Some(true)));
-                        let hash = context.node_from_index(n.index);
+                        let hash = context.hash_from_index(n.index);
                         match results.get(&n.index) {
                             Some(res) => format!("{} ({})", hash, res),
                             None => hash,
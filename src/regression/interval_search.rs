@@ -0,0 +1,470 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use priority_queue::DoublePriorityQueue;
+
+use crate::graph::length_of_path;
+
+use super::{RegressionPoint, TestResult};
+
+pub type SampleFunction =
+    fn(&VecDeque<String>, &String, &String, usize) -> Result<VecDeque<String>, ()>;
+
+/// How the samples of an interval search are scheduled on the processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheduling {
+    /// Takes a sample per free process and continues with the next step, as
+    /// soon as the new interval is known. Running jobs outside of it are
+    /// interrupted.
+    Eager,
+    /// Like `Eager`, but only continues when all jobs of the step are done.
+    WaitForAll,
+    /// Like `Eager`, but takes samples for the most processes that were free
+    /// so far.
+    MaxCapacity,
+    /// Takes one sample at a time instead of whole steps. Each sample is the
+    /// commit that leaves the shortest interval expected when it finishes,
+    /// given the running jobs and how long they have been running, so slow
+    /// processes don't hold back the search.
+    CostAware,
+}
+
+impl Scheduling {
+    pub const NAMES: [&'static str; 4] = ["eager", "wait-for-all", "max-capacity", "cost-aware"];
+}
+
+impl FromStr for Scheduling {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "eager" => Ok(Scheduling::Eager),
+            "wait-for-all" => Ok(Scheduling::WaitForAll),
+            "max-capacity" => Ok(Scheduling::MaxCapacity),
+            "cost-aware" => Ok(Scheduling::CostAware),
+            _ => Err(format!(
+                "Unknown scheduling {}! Pick ({})",
+                name,
+                Scheduling::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+pub struct IntervalSearch {
+    pub scheduling: Scheduling,
+    pub path: VecDeque<String>,
+    pub original_path: VecDeque<String>,
+    pub target: String,
+    pub left: String,
+    pub right: String,
+    pub step: Option<Step>,
+    pub regression: Option<String>,
+    pub results: HashMap<String, TestResult>,
+    pub interrupts: Vec<String>,
+    /// Most processes that were free so far.
+    pub capacity: usize,
+    /// Running jobs and when they were started, without steps.
+    pub pending: HashMap<String, Instant>,
+    pub finished: u32,
+    pub duration: Duration,
+}
+
+pub struct Step {
+    pub job_queue: VecDeque<String>,
+    pub job_await: HashSet<String>,
+    pub jobs: VecDeque<String>,
+    pub valid_nodes: DoublePriorityQueue<String, usize>,
+}
+
+impl IntervalSearch {
+    pub fn new(path: VecDeque<String>, scheduling: Scheduling) -> Self {
+        if path.len() <= 1 {
+            panic!("Path is too short for a regression point!");
+        }
+
+        let left = path.front().unwrap().clone();
+        let right = path.back().unwrap().clone();
+
+        let mut results = HashMap::new();
+        results.insert(left.clone(), TestResult::True);
+        results.insert(right.clone(), TestResult::False);
+
+        let mut bin = IntervalSearch {
+            scheduling,
+            original_path: path.clone(),
+            path,
+            target: right.to_string(),
+            left,
+            right,
+            regression: None,
+            step: None,
+            results,
+            interrupts: vec![],
+            capacity: 1,
+            pending: HashMap::new(),
+            finished: 0,
+            duration: Duration::ZERO,
+        };
+
+        bin.check_done();
+
+        bin
+    }
+
+    pub fn add_result(&mut self, commit: String, result: TestResult) {
+        if self.scheduling == Scheduling::CostAware {
+            self.add_sample_result(commit, result);
+            return;
+        }
+
+        if self.step.is_none() {
+            //Without running jobs, e.g. for results known before the search.
+            self.narrow(commit, result);
+            self.check_done();
+        } else {
+            let step = self.step.as_mut().unwrap();
+            self.results.insert(commit.clone(), result.clone());
+            if step.job_await.remove(&commit) {
+                if result == TestResult::True {
+                    for (i, h) in step.jobs.iter().enumerate() {
+                        if *h == commit {
+                            step.valid_nodes.push(commit.clone(), i + 1);
+                            break;
+                        }
+                    }
+                }
+
+                if self.scheduling == Scheduling::WaitForAll && !step.job_await.is_empty() {
+                    return;
+                }
+
+                // Traverse from the lowest valid job (highest index) to the
+                // next invalid job. If every job in between has a result,
+                // then we found the lowest regression. (We artificially add the
+                // left and right boarder as a job to this process.)
+                let jobs_len = step.jobs.len();
+                let (lowest_valid, i) = self
+                    .step
+                    .as_ref()
+                    .unwrap()
+                    .valid_nodes
+                    .peek_max()
+                    .unwrap_or((&self.left, &0));
+
+                let mut regression = None;
+
+                let mut incomplete = false;
+                for hash in self.step.as_ref().unwrap().jobs.range(*i..jobs_len) {
+                    match self.results.get(hash) {
+                        Some(res) => {
+                            if res == &TestResult::False {
+                                regression = Some(hash.to_string());
+                                break;
+                            }
+                        }
+                        None => {
+                            incomplete = true;
+                            break;
+                        }
+                    }
+                }
+                if !incomplete {
+                    if let Some(reg_point) = regression {
+                        self.right = reg_point;
+                    }
+                    self.left = lowest_valid.to_string();
+                    self.interrupts
+                        .extend(self.step.as_ref().unwrap().job_await.iter().cloned());
+                    self.clean_path();
+                    self.step = None;
+                    self.check_done();
+                }
+            } else {
+                eprintln!("Result for {} is not expected. Will ignore it!", commit);
+            }
+        }
+    }
+
+    pub fn next_job(
+        &mut self,
+        capacity: usize,
+        take_samples: SampleFunction,
+    ) -> super::AlgorithmResponse<'_> {
+        if self.scheduling == Scheduling::CostAware {
+            return self.next_sample();
+        }
+
+        self.capacity = std::cmp::max(self.capacity, capacity);
+        let sample_size = match self.scheduling {
+            Scheduling::MaxCapacity => self.capacity,
+            _ => capacity,
+        };
+
+        if self.step.is_none() {
+            let jobs = take_samples(&self.path, &self.left, &self.right, sample_size)
+                .expect("couldn't take samples!");
+
+            let step = Step {
+                job_queue: jobs.clone(),
+                job_await: HashSet::new(),
+                jobs,
+                valid_nodes: DoublePriorityQueue::new(),
+            };
+
+            self.step = Some(step);
+        }
+
+        let step_mut = self.step.as_mut().unwrap();
+
+        match step_mut.job_queue.pop_back() {
+            Some(job) => {
+                step_mut.job_await.insert(job.clone());
+                super::AlgorithmResponse::Job(job)
+            }
+            None => {
+                if step_mut.job_await.is_empty() {
+                    super::AlgorithmResponse::InternalError("Next step missing!")
+                } else {
+                    super::AlgorithmResponse::WaitForResult
+                }
+            }
+        }
+    }
+
+    pub fn interrupts(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.interrupts)
+    }
+
+    pub fn done(&self) -> bool {
+        self.regression.is_some()
+    }
+
+    pub fn results(&self) -> Vec<RegressionPoint> {
+        match self.regression.as_ref() {
+            Some(reg) => vec![RegressionPoint {
+                regression_point: reg.to_string(),
+                target: self.target.clone(),
+                candidates: self.candidates(),
+                probabilities: vec![],
+            }],
+            None => vec![],
+        }
+    }
+
+    fn add_sample_result(&mut self, commit: String, result: TestResult) {
+        //Results of other commits narrow the interval just the same.
+        if let Some(start) = self.pending.remove(&commit) {
+            self.finished += 1;
+            self.duration += start.elapsed();
+        }
+        self.narrow(commit, result);
+
+        //Jobs outside of the new interval can't tell anything new.
+        let (left, right) = self.bounds();
+        let outside = self
+            .pending
+            .keys()
+            .filter(|hash| match self.position(hash) {
+                Some(index) => index <= left || right <= index,
+                None => true,
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for hash in outside {
+            self.pending.remove(&hash);
+            self.interrupts.push(hash);
+        }
+
+        self.check_done();
+    }
+
+    fn next_sample(&mut self) -> super::AlgorithmResponse<'_> {
+        let (left, right) = self.bounds();
+
+        //Chance that the result of a running job is known when a new job
+        //finishes. Without finished jobs, all running jobs are expected to
+        //finish in time. A job that already ran k times as long as the
+        //finished jobs did on average finishes in time with a chance of 1/k.
+        let expected = (self.finished > 0).then(|| self.duration / self.finished);
+        let mut points = self
+            .pending
+            .iter()
+            .filter_map(|(hash, start)| {
+                let index = self.position(hash).filter(|i| left < *i && *i < right)?;
+                let elapsed = start.elapsed();
+                let chance = match expected {
+                    Some(expected) if elapsed > expected => {
+                        expected.as_secs_f64() / elapsed.as_secs_f64()
+                    }
+                    _ => 1.0,
+                };
+                Some((index, chance))
+            })
+            .collect::<Vec<_>>();
+        points.extend([(left, 1.0), (right, 1.0)]);
+        points.sort_by_key(|(index, _)| *index);
+
+        //Expected position of the first known result, the bounds are known.
+        let first_known = |points: &mut dyn Iterator<Item = &(usize, f64)>| {
+            let mut position = 0.0;
+            let mut unknown = 1.0;
+            for (index, chance) in points {
+                position += unknown * chance * *index as f64;
+                unknown *= 1.0 - chance;
+            }
+            position
+        };
+
+        //The regression point is equally likely any commit of the interval.
+        //When the new job finishes, the commits of the gap (a, b) between
+        //running jobs are in an interval of the expected length er - el. A
+        //sample c splits it into c - el and er - c, so the expected length
+        //summed over the commits of the gap shrinks from (b - a)(er - el) to
+        //(c - a)(c - el) + (b - c)(er - c), which is least for
+        //c = (a + b + el + er) / 4. The sample that shrinks it most is taken.
+        let mut best: Option<(f64, usize)> = None;
+        for k in 0..points.len() - 1 {
+            let (a, b) = (points[k].0, points[k + 1].0);
+            if b - a <= 1 {
+                continue;
+            }
+            let el = first_known(&mut points[..=k].iter().rev());
+            let er = first_known(&mut points[k + 1..].iter());
+            let (a, b) = (a as f64, b as f64);
+            let optimum = (a + b + el + er) / 4.0;
+            for c in [optimum.floor(), optimum.ceil()] {
+                let c = c.clamp(a + 1.0, b - 1.0);
+                let gain = (b - a) * (er - el) - (c - a) * (c - el) - (b - c) * (er - c);
+                if best.is_none_or(|(best, _)| gain > best) {
+                    best = Some((gain, c as usize));
+                }
+            }
+        }
+
+        let job = best.map(|(_, c)| self.path[c].clone());
+
+        match job {
+            Some(job) => {
+                self.pending.insert(job.clone(), Instant::now());
+                super::AlgorithmResponse::Job(job)
+            }
+            None => {
+                if self.pending.is_empty() {
+                    super::AlgorithmResponse::InternalError("Next step missing!")
+                } else {
+                    super::AlgorithmResponse::WaitForResult
+                }
+            }
+        }
+    }
+
+    fn narrow(&mut self, commit: String, result: TestResult) {
+        self.results.insert(commit.clone(), result.clone());
+
+        let (left, right) = self.bounds();
+        match self.position(&commit) {
+            Some(index) if left < index && index < right => match result {
+                TestResult::True => self.left = commit,
+                TestResult::False => self.right = commit,
+                TestResult::Ignore => {}
+            },
+            _ => {}
+        }
+        self.clean_path();
+    }
+
+    fn position(&self, hash: &String) -> Option<usize> {
+        self.path.iter().position(|h| h == hash)
+    }
+
+    fn bounds(&self) -> (usize, usize) {
+        let left = self.position(&self.left).expect("left is not on the path!");
+        let right = self
+            .position(&self.right)
+            .expect("right is not on the path!");
+        (left, right)
+    }
+
+    fn check_done(&mut self) {
+        match length_of_path(&self.path, &self.left, &self.right) {
+            Ok(len) => {
+                if len <= 2 {
+                    self.regression = Some(self.right.to_string());
+                }
+            }
+            Err(_) => panic!("Error at calculation length of path!"),
+        }
+    }
+
+    //Skipped commits between the last valid commit and the regression point
+    //were removed from the path, but could be the regression point as well.
+    fn candidates(&self) -> Vec<String> {
+        let mut candidates = self
+            .original_path
+            .iter()
+            .skip_while(|hash| **hash != self.left)
+            .skip(1)
+            .take_while(|hash| **hash != self.right)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !candidates.is_empty() {
+            candidates.push(self.right.clone());
+        }
+        candidates
+    }
+
+    fn clean_path(&mut self) {
+        self.path.retain(|hash| match self.results.get(hash) {
+            Some(res) => res != &TestResult::Ignore,
+            None => true,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regression::AlgorithmResponse;
+
+    const AVERAGE: Duration = Duration::from_millis(100);
+
+    //A path of 101 commits, finished jobs took `AVERAGE` and the running jobs
+    //at the given indices already took the given multiple of it.
+    fn next_sample(running: &[(usize, u32)]) -> usize {
+        let path = (0..=100).map(|i| i.to_string()).collect();
+        let mut search = IntervalSearch::new(path, Scheduling::CostAware);
+        search.finished = 1;
+        search.duration = AVERAGE;
+        for (index, factor) in running {
+            let start = Instant::now().checked_sub(AVERAGE * *factor).unwrap();
+            search.pending.insert(index.to_string(), start);
+        }
+        match search.next_sample() {
+            AlgorithmResponse::Job(job) => job.parse().unwrap(),
+            response => panic!("Unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn running_jobs_split_the_interval() {
+        assert!([25, 75].contains(&next_sample(&[(50, 0)])));
+    }
+
+    #[test]
+    fn slow_jobs_barely_split_the_interval() {
+        let sample = next_sample(&[(50, 10)]);
+        assert!((30..=70).contains(&sample), "sample {}", sample);
+    }
+
+    #[test]
+    fn samples_avoid_the_half_of_a_slow_job() {
+        //Taken as finished, the job at 25 would lead to a sample at 62.
+        let sample = next_sample(&[(25, 10)]);
+        assert!(25 < sample && sample < 62, "sample {}", sample);
+    }
+}
@@ -4,7 +4,7 @@ use priority_queue::DoublePriorityQueue;
 
 use crate::regression::TestResult;
 
-use super::{PathAlgorithm, RegressionAlgorithm, RegressionPoint};
+use super::{interval_search::Scheduling, PathAlgorithm, RegressionAlgorithm, RegressionPoint};
 
 pub struct LinearSearch {
     path: VecDeque<String>,
@@ -17,7 +17,8 @@ pub struct LinearSearch {
 }
 
 impl PathAlgorithm for LinearSearch {
-    fn new(path: VecDeque<String>) -> Self {
+    //Tests one commit after the other, there is nothing to schedule.
+    fn new(path: VecDeque<String>, _: Scheduling) -> Self {
         if path.len() <= 1 {
             panic!("Path is too short for a regression point!");
         }
@@ -49,42 +50,61 @@ impl PathAlgorithm for LinearSearch {
 
 impl RegressionAlgorithm for LinearSearch {
     fn add_result(&mut self, commit: String, result: super::TestResult) {
-        match self.job_await.remove(&commit) {
-            Some(index) => {
-                self.results[index] = Some(result.clone());
-                if result == TestResult::True {
-                    self.valid_nodes.push(commit, index);
+        let index = match self.job_await.remove(&commit) {
+            Some(index) => index,
+            //Results the search didn't ask for, e.g. known before the search.
+            //Commits above the current index were skipped, as they can't be
+            //the regression point.
+            None => match self.path.iter().position(|hash| *hash == commit) {
+                Some(index) if index <= self.index && self.results[index].is_none() => {
+                    //Like an interval search, the search continues below an
+                    //invalid commit.
+                    if result == TestResult::False {
+                        self.index = std::cmp::min(self.index, index - 1);
+                    }
+                    index
+                }
+                _ => {
+                    eprintln!("Result for {} is not expected. Will ignore it!", commit);
+                    return;
                 }
-                // Traverse from the lowest valid node (highest index) to the next invalid node.
-                // If every commit in between has a result, then we found the
-                // lowest regression point
-                let (_, i) = self.valid_nodes.peek_max().unwrap();
-                for (ni, hash) in self
-                    .path
-                    .range((*i + 1)..self.path.len())
-                    .enumerate()
-                {
-                    match &self.results[i + 1 + ni] {
-                        Some(res) => {
-                            if res == &TestResult::False {
-                                let inters = self.job_await.keys().map(|a| a.to_string());
-                                self.interrupts.extend(inters);
-                                self.regression_point = Some(hash.to_string());
-                                break;
-                            }
-                        }
-                        None => break,
+            },
+        };
+
+        self.results[index] = Some(result.clone());
+        if result == TestResult::True {
+            self.valid_nodes.push(commit, index);
+        }
+        // Traverse from the lowest valid node (highest index) to the next invalid node.
+        // If every commit in between has a result, then we found the
+        // lowest regression point
+        let (_, i) = self.valid_nodes.peek_max().unwrap();
+        for (ni, hash) in self.path.range((*i + 1)..self.path.len()).enumerate() {
+            match &self.results[i + 1 + ni] {
+                Some(res) => {
+                    if res == &TestResult::False {
+                        let inters = self.job_await.keys().map(|a| a.to_string());
+                        self.interrupts.extend(inters);
+                        self.regression_point = Some(hash.to_string());
+                        break;
                     }
                 }
+                None => break,
             }
-            None => eprintln!("Result for {} is not expected. Will ignore it!", commit),
         }
     }
 
-    fn next_job(&mut self, _: u32, _: u32) -> super::AlgorithmResponse {
-        //If there are still unchecked nodes and no regression point has been
-        //found yet, then we want to continue with the next node on the path.
-        if self.index > 0 && self.valid_nodes.len() < 2 {
+    fn next_job(&mut self, _: u32, _: u32) -> super::AlgorithmResponse<'_> {
+        //Commits with a known result are skipped.
+        let (_, lowest_valid) = self.valid_nodes.peek_max().unwrap();
+        let lowest_valid = *lowest_valid;
+        while self.index > lowest_valid && self.results[self.index].is_some() {
+            self.index -= 1;
+        }
+
+        //If there are still unchecked nodes above the lowest valid node, then
+        //we want to continue with the next node on the path.
+        if self.index > lowest_valid {
             let commit = self.path.get(self.index).unwrap();
             self.job_await.insert(commit.to_string(), self.index);
             self.index -= 1;
@@ -113,9 +133,25 @@ impl RegressionAlgorithm for LinearSearch {
             .expect("No regression point!")
             .to_string();
         let target = self.path.back().unwrap().clone();
+
+        //Everything between the lowest valid commit and the regression point
+        //has been skipped.
+        let (_, lowest_valid) = self.valid_nodes.peek_max().unwrap();
+        let mut candidates = self
+            .path
+            .range((lowest_valid + 1)..self.path.len())
+            .take_while(|hash| **hash != regression_point)
+            .cloned()
+            .collect::<Vec<_>>();
+        if !candidates.is_empty() {
+            candidates.push(regression_point.clone());
+        }
+
         vec![RegressionPoint {
             regression_point,
             target,
+            candidates,
+            probabilities: vec![],
         }]
     }
 }
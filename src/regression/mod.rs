@@ -1,5 +1,9 @@
 use std::{collections::VecDeque, fmt};
 
+use serde::Serialize;
+
+use self::interval_search::Scheduling;
+
 mod rpa_extension;
 mod generalized_rpa;
 
@@ -11,23 +15,15 @@ pub mod rpa_search {
 }
 
 pub mod git_bisect;
+pub mod bayesian_search;
 
 pub mod binary_search;
+pub mod galloping_search;
 pub mod linear_search;
 pub mod multiplying_search;
-mod interval_search1;
-// mod interval_search2;
-//mod interval_search3;
-mod interval_search {
-    pub use super::interval_search1::*;
-    // pub use super::interval_search2::*;
-    // pub use super::interval_search3::*;
-}
-
-pub const NAME: &str = interval_search::NAME; 
-
+pub mod interval_search;
 
-#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+#[derive(Debug, Clone, PartialEq, Hash, Eq, Serialize)]
 pub enum TestResult {
     True,
     False,
@@ -51,20 +47,65 @@ pub enum AlgorithmResponse<'a> {
     InternalError(&'a str)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RegressionPoint {
     pub target: String,
     pub regression_point: String,
+    /// If untestable commits separate the regression point from the last good
+    /// commits, any of them could be the regression point. Contains all these
+    /// candidates including the regression point, otherwise it's empty.
+    pub candidates: Vec<String>,
+    /// Most likely regression points, if the search can't be certain, e.g.
+    /// for flaky tests. Otherwise it's empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub probabilities: Vec<Probability>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Probability {
+    pub commit: String,
+    pub probability: f64,
+}
+
+impl RegressionPoint {
+    pub fn is_ambiguous(&self) -> bool {
+        !self.candidates.is_empty()
+    }
+}
+
+/// Narrowest known interval for the regression point of a target.
+#[derive(Debug, Clone, Serialize)]
+pub struct Interval {
+    pub target: String,
+    pub last_good: Vec<String>,
+    pub first_bad: String,
 }
 
 pub trait RegressionAlgorithm {
     fn add_result(&mut self, commit: String, result: TestResult);
-    fn next_job(&mut self, capacity: u32, expected_capacity: u32) -> AlgorithmResponse;
+    fn next_job(&mut self, capacity: u32, expected_capacity: u32) -> AlgorithmResponse<'_>;
     fn interrupts(&mut self) -> Vec<String>;
     fn done(&self) -> bool;
     fn results(&self) -> Vec<RegressionPoint>;
+
+    /// Intervals of all targets without a regression point so far. Only
+    /// needed for algorithms that are used to drive a whole search.
+    fn intervals(&self) -> Vec<Interval> {
+        vec![]
+    }
+
+    /// Path of the current search, if the algorithm searches along paths.
+    fn current_path(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Whether a commit can be tested again, e.g. because the test is flaky.
+    /// Results are neither cached nor reused then.
+    fn repeats_tests(&self) -> bool {
+        false
+    }
 }
 
 pub trait PathAlgorithm {
-    fn new(path: VecDeque<String>) -> Self;
+    fn new(path: VecDeque<String>, scheduling: Scheduling) -> Self;
 }
\ No newline at end of file
@@ -1,15 +1,18 @@
 use std::collections::VecDeque;
 
-use super::{interval_search::IntervalSearch, PathAlgorithm, RegressionAlgorithm};
+use super::{
+    interval_search::{IntervalSearch, Scheduling},
+    PathAlgorithm, RegressionAlgorithm,
+};
 
 pub struct MultiplyingSearch {
     search: IntervalSearch,
 }
 
 impl PathAlgorithm for MultiplyingSearch {
-    fn new(path: VecDeque<String>) -> Self {
+    fn new(path: VecDeque<String>, scheduling: Scheduling) -> Self {
         MultiplyingSearch {
-            search: IntervalSearch::new(path),
+            search: IntervalSearch::new(path, scheduling),
         }
     }
 }
@@ -19,7 +22,7 @@ impl RegressionAlgorithm for MultiplyingSearch {
         self.search.add_result(commit, result);
     }
 
-    fn next_job(&mut self, _: u32, expected_capacity: u32) -> super::AlgorithmResponse {
+    fn next_job(&mut self, _: u32, expected_capacity: u32) -> super::AlgorithmResponse<'_> {
         self.search
             .next_job(expected_capacity as usize, take_samples)
     }
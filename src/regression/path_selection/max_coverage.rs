@@ -0,0 +1,117 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use daggy::{NodeIndex, Walker};
+use priority_queue::PriorityQueue;
+
+use crate::regression::rpa_util::Commits;
+
+use super::{topological_order, weighted::WeightedPaths, PathSelection};
+
+/// Picks the path that covers the most untested commits, each weighted by the
+/// number of targets that descend from it. A regression point found there
+/// narrows down the search for many targets at once.
+pub struct MaxCoverage;
+
+#[derive(Default)]
+pub struct Distances {
+    paths: WeightedPaths,
+    //Number of targets that descend from each node of the region.
+    reached: HashMap<NodeIndex, i32>,
+    //The targets the weights were calculated for.
+    targets: HashSet<NodeIndex>,
+}
+
+//Tested commits don't count, e.g. skipped ones or the targets.
+fn weight<E>(commits: &Commits<E>, reached: &HashMap<NodeIndex, i32>, index: NodeIndex) -> i32 {
+    match commits.result(index) {
+        Some(_) => 0,
+        None => reached.get(&index).copied().unwrap_or(0),
+    }
+}
+
+impl PathSelection for MaxCoverage {
+    type Distances = Distances;
+
+    fn calculate_distances<E: Clone>(
+        commits: &Commits<E>,
+        targets: &HashSet<NodeIndex>,
+        valid_nodes: &HashSet<NodeIndex>,
+        distances: &mut Distances,
+    ) -> PriorityQueue<(NodeIndex, NodeIndex), i32> {
+        let graph = &commits.graph;
+        distances.paths.reset(graph, targets, valid_nodes);
+        let order = topological_order(graph, distances.paths.region());
+
+        //The targets that descend from each node, as a bit set.
+        let positions = targets
+            .iter()
+            .enumerate()
+            .map(|(position, target)| (*target, position))
+            .collect::<HashMap<_, _>>();
+        let words = targets.len().div_ceil(64);
+        let mut bit_sets = HashMap::<NodeIndex, Vec<u64>>::new();
+        for current in order.iter().rev() {
+            let mut bits = vec![0; words];
+            if let Some(position) = positions.get(current) {
+                bits[position / 64] |= 1 << (position % 64);
+            }
+            for (_, child) in graph.graph.children(*current).iter(&graph.graph) {
+                if let Some(child_bits) = bit_sets.get(&child) {
+                    for (word, child_word) in bits.iter_mut().zip(child_bits) {
+                        *word |= child_word;
+                    }
+                }
+            }
+            bit_sets.insert(*current, bits);
+        }
+
+        distances.reached = bit_sets
+            .into_iter()
+            .map(|(index, bits)| {
+                (
+                    index,
+                    bits.iter().map(|word| word.count_ones() as i32).sum(),
+                )
+            })
+            .collect();
+        distances.targets = targets.clone();
+
+        let reached = &distances.reached;
+        distances
+            .paths
+            .calculate(graph, targets, |index| weight(commits, reached, index))
+    }
+
+    //The weights stay the same, as long as no regression point was found. The
+    //weights of commits tested in the meantime are updated with the paths
+    //through them.
+    fn update_distances<E: Clone>(
+        commits: &Commits<E>,
+        targets: &HashSet<NodeIndex>,
+        valid_nodes: &HashSet<NodeIndex>,
+        distances: &mut Distances,
+        ordering: &mut PriorityQueue<(NodeIndex, NodeIndex), i32>,
+        node: NodeIndex,
+    ) {
+        if targets != &distances.targets {
+            *ordering = Self::calculate_distances(commits, targets, valid_nodes, distances);
+            return;
+        }
+
+        let reached = &distances.reached;
+        distances
+            .paths
+            .update(&commits.graph, targets, ordering, node, |index| {
+                weight(commits, reached, index)
+            });
+    }
+
+    fn extract_path<E>(
+        _: &Commits<E>,
+        distances: &Distances,
+        source: NodeIndex,
+        target: NodeIndex,
+    ) -> VecDeque<NodeIndex> {
+        distances.paths.path(source, target)
+    }
+}
@@ -1,14 +1,95 @@
-use std::collections::{VecDeque, HashSet};
+use std::collections::{HashMap, VecDeque, HashSet};
 
-use daggy::NodeIndex;
+use daggy::{NodeIndex, Walker};
 use priority_queue::PriorityQueue;
 use crate::graph::Adag;
-use super::rpa_util::RPANode;
+use super::rpa_util::Commits;
 
 pub mod shortest_path;
 pub mod longest_path;
+pub mod max_coverage;
+mod weighted;
+#[cfg(test)]
+mod tests;
 
 pub trait PathSelection {
-    fn calculate_distances<E: Clone>(graph: &Adag<RPANode, E>, targets: &HashSet<NodeIndex>, valid_nodes: &HashSet<NodeIndex>) -> PriorityQueue<(NodeIndex, NodeIndex), i32>;
-    fn extract_path<E>(graph: &Adag<RPANode, E>, source: NodeIndex, target: NodeIndex) -> VecDeque<NodeIndex>;
-}
\ No newline at end of file
+    /// What a selection keeps between the results of a search, to update the
+    /// distances instead of calculating them from scratch.
+    type Distances: Default;
+
+    fn calculate_distances<E: Clone>(commits: &Commits<E>, targets: &HashSet<NodeIndex>, valid_nodes: &HashSet<NodeIndex>, distances: &mut Self::Distances) -> PriorityQueue<(NodeIndex, NodeIndex), i32>;
+    fn extract_path<E>(commits: &Commits<E>, distances: &Self::Distances, source: NodeIndex, target: NodeIndex) -> VecDeque<NodeIndex>;
+
+    /// Updates `ordering` after `node` was found valid. By default, all
+    /// distances are calculated again.
+    fn update_distances<E: Clone>(
+        commits: &Commits<E>,
+        targets: &HashSet<NodeIndex>,
+        valid_nodes: &HashSet<NodeIndex>,
+        distances: &mut Self::Distances,
+        ordering: &mut PriorityQueue<(NodeIndex, NodeIndex), i32>,
+        _node: NodeIndex,
+    ) {
+        *ordering = Self::calculate_distances(commits, targets, valid_nodes, distances);
+    }
+}
+
+/// Ancestors of the targets that aren't valid, the only nodes that paths from
+/// the valid nodes to the targets go through.
+pub fn region<E>(
+    graph: &Adag<E>,
+    targets: &HashSet<NodeIndex>,
+    valid: &HashSet<NodeIndex>,
+) -> HashSet<NodeIndex> {
+    let mut region: HashSet<NodeIndex> = targets
+        .iter()
+        .filter(|target| !valid.contains(target))
+        .cloned()
+        .collect();
+
+    let mut queue = Vec::from_iter(region.iter().cloned());
+    while let Some(current) = queue.pop() {
+        for (_, parent) in graph.graph.parents(current).iter(&graph.graph) {
+            if !valid.contains(&parent) && region.insert(parent) {
+                queue.push(parent);
+            }
+        }
+    }
+    region
+}
+
+/// Orders `nodes`, so that parents come before their children. Only the edges
+/// between `nodes` are considered.
+pub fn topological_order<E>(graph: &Adag<E>, nodes: &HashSet<NodeIndex>) -> Vec<NodeIndex> {
+    let mut waiting = HashMap::new();
+    let mut queue = VecDeque::new();
+    let mut order = Vec::with_capacity(nodes.len());
+
+    for node in nodes {
+        let parents = graph
+            .graph
+            .parents(*node)
+            .iter(&graph.graph)
+            .filter(|(_, parent)| nodes.contains(parent))
+            .count();
+        if parents == 0 {
+            queue.push_back(*node);
+        } else {
+            waiting.insert(*node, parents);
+        }
+    }
+
+    while let Some(current) = queue.pop_front() {
+        order.push(current);
+        for (_, child) in graph.graph.children(current).iter(&graph.graph) {
+            if let Some(parents) = waiting.get_mut(&child) {
+                *parents -= 1;
+                if *parents == 0 {
+                    waiting.remove(&child);
+                    queue.push_back(child);
+                }
+            }
+        }
+    }
+    order
+}
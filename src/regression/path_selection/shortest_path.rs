@@ -3,18 +3,28 @@ use std::collections::{HashMap, HashSet, VecDeque, hash_map::Entry::Vacant};
 use daggy::{NodeIndex, Walker};
 use priority_queue::PriorityQueue;
 
-use crate::{graph::Adag, regression::rpa_util::RPANode};
+use crate::regression::rpa_util::Commits;
 
 use super::PathSelection;
 
 pub struct ShortestPath;
 
+/// Distance of each reachable node to its nearest valid node.
+#[derive(Default)]
+pub struct Distances {
+    nearest: HashMap<NodeIndex, (NodeIndex, i32)>,
+}
+
 impl PathSelection for ShortestPath {
+    type Distances = Distances;
+
     fn calculate_distances<E: Clone>(
-        graph: &Adag<RPANode, E>,
+        commits: &Commits<E>,
         targets: &HashSet<NodeIndex>,
         valid_nodes: &HashSet<NodeIndex>,
+        distances: &mut Distances,
     ) -> PriorityQueue<(NodeIndex, NodeIndex), i32> {
+        let graph = &commits.graph;
         let mut shortest_path = PriorityQueue::new();
         let targets_indices: HashSet<NodeIndex> = targets.clone();
         let mut queue: VecDeque<(NodeIndex, NodeIndex, i32)> = VecDeque::new();
@@ -25,9 +35,13 @@ impl PathSelection for ShortestPath {
             visited.insert(*index);
         }
 
+        distances.nearest.clear();
         while !queue.is_empty() {
             let (current_index, current_parent_index, current_distance) =
                 queue.pop_front().unwrap();
+            distances
+                .nearest
+                .insert(current_index, (current_parent_index, current_distance));
             if targets_indices.contains(&current_index) {
                 shortest_path.push((current_parent_index, current_index), -current_distance);
             }
@@ -42,11 +56,45 @@ impl PathSelection for ShortestPath {
         shortest_path
     }
 
+    //A new valid node only shortens the distances of its descendants, the
+    //search stops at nodes that are closer to another valid node.
+    fn update_distances<E: Clone>(
+        commits: &Commits<E>,
+        targets: &HashSet<NodeIndex>,
+        _: &HashSet<NodeIndex>,
+        distances: &mut Distances,
+        ordering: &mut PriorityQueue<(NodeIndex, NodeIndex), i32>,
+        node: NodeIndex,
+    ) {
+        let graph = &commits.graph;
+        let mut queue = VecDeque::from([(node, 0)]);
+
+        while let Some((current, distance)) = queue.pop_front() {
+            match distances.nearest.get(&current) {
+                Some((_, known)) if *known <= distance => continue,
+                Some((start, _)) if targets.contains(&current) => {
+                    ordering.remove(&(*start, current));
+                }
+                _ => {}
+            }
+            distances.nearest.insert(current, (node, distance));
+            if targets.contains(&current) {
+                ordering.push((node, current), -distance);
+            }
+
+            for (_, child) in graph.graph.children(current).iter(&graph.graph) {
+                queue.push_back((child, distance + 1));
+            }
+        }
+    }
+
     fn extract_path<E>(
-        graph: &Adag<RPANode, E>,
+        commits: &Commits<E>,
+        _: &Distances,
         source: NodeIndex,
         target: NodeIndex,
     ) -> std::collections::VecDeque<daggy::NodeIndex> {
+        let graph = &commits.graph;
         let mut queue = VecDeque::new();
         let mut parent = HashMap::new();
 
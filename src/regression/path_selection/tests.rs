@@ -0,0 +1,197 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use daggy::{Dag, NodeIndex};
+use priority_queue::PriorityQueue;
+
+use crate::{
+    graph::{valid_closure, Adag, Oid},
+    regression::{rpa_util::Commits, TestResult},
+};
+
+use super::{
+    longest_path::LongestPath, max_coverage::MaxCoverage, shortest_path::ShortestPath,
+    PathSelection,
+};
+
+//Xorshift, so that the graphs are the same on every run.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+//Each commit has a parent among the 50 commits before it, `merges` percent of
+//them have a second parent. The first commit is the source, the targets are
+//five of the last commits.
+fn random_commits(nodes: usize, seed: u64, merges: usize) -> (Commits<()>, HashSet<NodeIndex>) {
+    let mut random = Random(seed);
+    let mut dag = Dag::<Oid, ()>::new();
+    let mut indices = vec![];
+    for i in 0..nodes {
+        let mut bytes = [0; 20];
+        bytes[..8].copy_from_slice(&(i as u64).to_be_bytes());
+        let index = dag.add_node(Oid::from(bytes));
+        if i > 0 {
+            let first = i.saturating_sub(50);
+            let parent = first + random.below(i - first);
+            dag.add_edge(indices[parent], index, ()).unwrap();
+            if random.below(100) < merges {
+                let other = first + random.below(i - first);
+                if other != parent {
+                    dag.add_edge(indices[other], index, ()).unwrap();
+                }
+            }
+        }
+        indices.push(index);
+    }
+
+    let targets = (0..5)
+        .map(|k| indices[nodes - 1 - k * 7])
+        .collect::<HashSet<_>>();
+    let mut results = HashMap::from([(indices[0], TestResult::True)]);
+    for target in &targets {
+        results.insert(*target, TestResult::False);
+    }
+    let commits = Commits {
+        graph: Adag::new(dag, vec![], vec![]),
+        results,
+    };
+    (commits, targets)
+}
+
+//The start of a path may differ between paths of the same weight.
+fn priorities(
+    ordering: &PriorityQueue<(NodeIndex, NodeIndex), i32>,
+    targets: &HashSet<NodeIndex>,
+) -> Vec<(NodeIndex, i32)> {
+    let mut priorities = ordering
+        .iter()
+        .filter(|((_, target), _)| targets.contains(target))
+        .map(|((_, target), priority)| (*target, *priority))
+        .collect::<Vec<_>>();
+    priorities.sort();
+    priorities
+}
+
+fn check_updates<P: PathSelection>() {
+    for seed in 1..30 {
+        let (mut commits, targets) = random_commits(400, seed * 7919, 20);
+        let mut valid_nodes = HashSet::from([NodeIndex::new(0)]);
+        let mut distances = P::Distances::default();
+        let mut ordering = P::calculate_distances(&commits, &targets, &valid_nodes, &mut distances);
+
+        let mut random = Random(seed + 1);
+        for _ in 0..25 {
+            let closure = valid_closure(&commits.graph, &valid_nodes);
+            let candidates = (0..400)
+                .map(NodeIndex::new)
+                .filter(|index| !closure.contains(index) && !targets.contains(index))
+                .collect::<Vec<_>>();
+            if candidates.is_empty() {
+                break;
+            }
+            let node = candidates[random.below(candidates.len())];
+            valid_nodes.insert(node);
+            commits.results.insert(node, TestResult::True);
+
+            P::update_distances(
+                &commits,
+                &targets,
+                &valid_nodes,
+                &mut distances,
+                &mut ordering,
+                node,
+            );
+            let mut full = P::Distances::default();
+            let expected = P::calculate_distances(&commits, &targets, &valid_nodes, &mut full);
+            assert_eq!(
+                priorities(&ordering, &targets),
+                priorities(&expected, &targets),
+                "seed {}, valid node {}",
+                seed,
+                node.index()
+            );
+        }
+    }
+}
+
+#[test]
+fn shortest_path_updates_match_calculation() {
+    check_updates::<ShortestPath>();
+}
+
+#[test]
+fn longest_path_updates_match_calculation() {
+    check_updates::<LongestPath>();
+}
+
+#[test]
+fn max_coverage_updates_match_calculation() {
+    check_updates::<MaxCoverage>();
+}
+
+//Average time of an update and of a full calculation, after each of 20 valid
+//commits found like a bisection.
+fn benchmark<P: PathSelection>(nodes: usize) -> (Duration, Duration) {
+    let (mut commits, targets) = random_commits(nodes, 42, 10);
+    let steps = (1..=20)
+        .map(|k: usize| NodeIndex::new(nodes / 2 - nodes / (2 << k.min(18))))
+        .collect::<Vec<_>>();
+
+    let mut valid_nodes = HashSet::from([NodeIndex::new(0)]);
+    let mut distances = P::Distances::default();
+    let mut ordering = P::calculate_distances(&commits, &targets, &valid_nodes, &mut distances);
+    let mut full = Duration::ZERO;
+    let mut incremental = Duration::ZERO;
+    for node in steps {
+        valid_nodes.insert(node);
+        commits.results.insert(node, TestResult::True);
+
+        let start = Instant::now();
+        P::update_distances(
+            &commits,
+            &targets,
+            &valid_nodes,
+            &mut distances,
+            &mut ordering,
+            node,
+        );
+        incremental += start.elapsed();
+
+        let start = Instant::now();
+        P::calculate_distances(
+            &commits,
+            &targets,
+            &valid_nodes,
+            &mut P::Distances::default(),
+        );
+        full += start.elapsed();
+    }
+    (full / 20, incremental / 20)
+}
+
+//cargo test --release benchmark_updates -- --ignored --nocapture
+#[test]
+#[ignore]
+fn benchmark_updates() {
+    for nodes in [10_000, 100_000, 1_000_000] {
+        for (selection, (full, incremental)) in [
+            ("shortest", benchmark::<ShortestPath>(nodes)),
+            ("longest", benchmark::<LongestPath>(nodes)),
+            ("cover", benchmark::<MaxCoverage>(nodes)),
+        ] {
+            println!(
+                "{:>9} nodes {:<8} full {:>10.2?} incremental {:>10.2?}",
+                nodes, selection, full, incremental
+            );
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+};
+
+use daggy::{NodeIndex, Walker};
+use priority_queue::PriorityQueue;
+
+use crate::graph::{valid_closure, Adag};
+
+use super::{region, topological_order};
+
+/// Heaviest paths from the valid nodes to the nodes between them and the
+/// targets. A path leaves the valid nodes (and their ancestors) right at its
+/// start, so all other commits on it are untested.
+#[derive(Default)]
+pub struct WeightedPaths {
+    valid: HashSet<NodeIndex>,
+    //Ancestors of the targets that aren't valid, paths only go through them.
+    region: HashSet<NodeIndex>,
+    //Nodes of the region without a path from a valid node have no entry.
+    best: HashMap<NodeIndex, Step>,
+    //Position of each node of the region in a topological order.
+    rank: HashMap<NodeIndex, usize>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Step {
+    weight: i32,
+    length: i32,
+    predecessor: NodeIndex,
+    start: NodeIndex,
+}
+
+impl Step {
+    //Paths with the same weight are searched faster, if they are shorter.
+    fn is_better(&self, other: &Step) -> bool {
+        self.weight > other.weight || (self.weight == other.weight && self.length < other.length)
+    }
+}
+
+impl WeightedPaths {
+    /// Forgets all paths and determines the region between `valid_nodes` and
+    /// `targets`.
+    pub fn reset<E>(
+        &mut self,
+        graph: &Adag<E>,
+        targets: &HashSet<NodeIndex>,
+        valid_nodes: &HashSet<NodeIndex>,
+    ) {
+        self.valid = valid_closure(graph, valid_nodes);
+        self.region = region(graph, targets, &self.valid);
+        self.best.clear();
+        self.rank.clear();
+    }
+
+    /// Calculates the paths to all nodes of the region.
+    pub fn calculate<E>(
+        &mut self,
+        graph: &Adag<E>,
+        targets: &HashSet<NodeIndex>,
+        weight: impl Fn(NodeIndex) -> i32,
+    ) -> PriorityQueue<(NodeIndex, NodeIndex), i32> {
+        let order = topological_order(graph, &self.region);
+        for current in &order {
+            if let Some(step) = self.step(graph, *current, &weight) {
+                self.best.insert(*current, step);
+            }
+        }
+        self.rank = order
+            .into_iter()
+            .enumerate()
+            .map(|(rank, index)| (index, rank))
+            .collect();
+
+        let mut ordering = PriorityQueue::new();
+        for target in targets {
+            if let Some(step) = self.best.get(target) {
+                ordering.push((step.start, *target), step.weight);
+            }
+        }
+        ordering
+    }
+
+    /// Updates the paths after `node` was found valid. Only the paths to its
+    /// descendants can change, they are visited in topological order until
+    /// their paths stay the same.
+    pub fn update<E>(
+        &mut self,
+        graph: &Adag<E>,
+        targets: &HashSet<NodeIndex>,
+        ordering: &mut PriorityQueue<(NodeIndex, NodeIndex), i32>,
+        node: NodeIndex,
+        weight: impl Fn(NodeIndex) -> i32,
+    ) {
+        //The node and its ancestors, that weren't known to be valid so far.
+        let mut new_valid = vec![];
+        if self.valid.insert(node) {
+            new_valid.push(node);
+        }
+        let mut queue = new_valid.clone();
+        while let Some(current) = queue.pop() {
+            for (_, parent) in graph.graph.parents(current).iter(&graph.graph) {
+                if self.valid.insert(parent) {
+                    new_valid.push(parent);
+                    queue.push(parent);
+                }
+            }
+        }
+
+        for index in &new_valid {
+            if let Some(step) = self.best.remove(index) {
+                ordering.remove(&(step.start, *index));
+            }
+            self.region.remove(index);
+        }
+
+        let mut queued = HashSet::new();
+        let mut queue = BinaryHeap::new();
+        for index in &new_valid {
+            self.queue_children(graph, *index, &mut queued, &mut queue);
+        }
+
+        while let Some(Reverse((_, current))) = queue.pop() {
+            let old = self.best.get(&current).copied();
+            let new = self.step(graph, current, &weight);
+            if old == new {
+                continue;
+            }
+
+            if targets.contains(&current) {
+                if let Some(step) = old {
+                    ordering.remove(&(step.start, current));
+                }
+                if let Some(step) = new {
+                    ordering.push((step.start, current), step.weight);
+                }
+            }
+            match new {
+                Some(step) => self.best.insert(current, step),
+                None => self.best.remove(&current),
+            };
+            self.queue_children(graph, current, &mut queued, &mut queue);
+        }
+    }
+
+    pub fn path(&self, source: NodeIndex, target: NodeIndex) -> VecDeque<NodeIndex> {
+        let mut path = VecDeque::from([target]);
+        let mut current = target;
+
+        while current != source {
+            match self.best.get(&current) {
+                Some(step) => {
+                    current = step.predecessor;
+                    path.push_front(current);
+                }
+                None => break,
+            }
+        }
+
+        path
+    }
+
+    pub fn region(&self) -> &HashSet<NodeIndex> {
+        &self.region
+    }
+
+    fn queue_children<E>(
+        &self,
+        graph: &Adag<E>,
+        index: NodeIndex,
+        queued: &mut HashSet<NodeIndex>,
+        queue: &mut BinaryHeap<Reverse<(usize, NodeIndex)>>,
+    ) {
+        for (_, child) in graph.graph.children(index).iter(&graph.graph) {
+            if self.region.contains(&child) && queued.insert(child) {
+                queue.push(Reverse((self.rank[&child], child)));
+            }
+        }
+    }
+
+    //The heaviest path to `index`, via the paths to its parents.
+    fn step<E>(
+        &self,
+        graph: &Adag<E>,
+        index: NodeIndex,
+        weight: impl Fn(NodeIndex) -> i32,
+    ) -> Option<Step> {
+        let weight = weight(index);
+        let mut best: Option<Step> = None;
+        for (_, parent) in graph.graph.parents(index).iter(&graph.graph) {
+            let step = if self.valid.contains(&parent) {
+                Some(Step {
+                    weight,
+                    length: 1,
+                    predecessor: parent,
+                    start: parent,
+                })
+            } else {
+                self.best.get(&parent).map(|step| Step {
+                    weight: step.weight + weight,
+                    length: step.length + 1,
+                    predecessor: parent,
+                    start: step.start,
+                })
+            };
+            match (step, best) {
+                (Some(step), Some(b)) if !step.is_better(&b) => {}
+                (Some(step), _) => best = Some(step),
+                (None, _) => {}
+            }
+        }
+        best
+    }
+}
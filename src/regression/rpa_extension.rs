@@ -2,11 +2,11 @@ use std::{collections::{HashSet, VecDeque}, marker::PhantomData};
 
 use daggy::{NodeIndex, Walker};
 
-use crate::{graph::Adag, log, regression::rpa_search::main_log_file};
+use crate::log::{self, Event};
 
 use super::{
     AlgorithmResponse, PathAlgorithm, RegressionAlgorithm, RegressionPoint,
-    TestResult, rpa_util::RPANode, path_selection::PathSelection,
+    TestResult, rpa_util::Commits, path_selection::PathSelection, interval_search::Scheduling,
 };
 
 pub struct ExtendedSearch<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> {
@@ -15,10 +15,11 @@ pub struct ExtendedSearch<P: PathSelection, S: PathAlgorithm + RegressionAlgorit
     interrupts: Vec<String>,
     regression: Option<String>,
     target: String,
-    graph: Adag<RPANode, E>,
+    commits: Commits<E>,
     valid_nodes: HashSet<NodeIndex>,
     log_path: Option<std::path::PathBuf>,
     counter: usize,
+    scheduling: Scheduling,
     _marker: PhantomData<P>,
 }
 
@@ -28,13 +29,14 @@ pub struct ParentsSearch {
 }
 
 impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> ExtendedSearch<P, S, E> {
-    pub fn new(adag: Adag<RPANode, E>, reg: RegressionPoint, valid_nodes: &HashSet<NodeIndex>, log_path: Option<std::path::PathBuf>, counter: usize) -> Self {
+    pub fn new(commits: Commits<E>, reg: RegressionPoint, valid_nodes: &HashSet<NodeIndex>, log_path: Option<std::path::PathBuf>, counter: usize, scheduling: Scheduling) -> Self {
         let mut q = VecDeque::<NodeIndex>::new();
         let mut queued = HashSet::<NodeIndex>::new();
 
         let mut p: VecDeque<String> = VecDeque::new();
         let mut cached_parent = None;
 
+        let adag = &commits.graph;
         let reg_index = adag.index(&reg.regression_point);
         q.push_back(reg_index);
         queued.insert(reg_index);
@@ -44,9 +46,7 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Extende
             let parents = adag.graph.parents(current_index);
 
             for (_, parent_index) in parents.iter(&adag.graph) {
-                let node = adag.node_from_index(parent_index);
-
-                match node.result {
+                match commits.result(parent_index) {
                     Some(result) => match result {
                         super::TestResult::False => {
                             cached_parent = Some(parent_index);
@@ -59,7 +59,7 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Extende
                         }
                         super::TestResult::True => {}
                     },
-                    None => p.push_back(node.hash),
+                    None => p.push_back(adag.hash_from_index(parent_index)),
                 }
             }
 
@@ -70,17 +70,18 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Extende
 
         if let Some(cp_index) = cached_parent {
             let cp = adag.hash_from_index(cp_index);
-            let search = create_sub::<P, S, E>(&adag, cp, valid_nodes, &log_path, counter);
+            let search = create_sub::<P, S, E>(&commits, cp, valid_nodes, &log_path, counter, scheduling);
             let mut search = ExtendedSearch {
                 parents: None,
                 sub: search,
                 interrupts: vec![],
                 regression: None,
                 target: reg.target,
-                graph: adag,
+                commits,
                 valid_nodes: valid_nodes.clone(),
                 log_path,
                 counter,
+                scheduling,
                 _marker: PhantomData,
             };
 
@@ -93,10 +94,11 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Extende
                 interrupts: vec![],
                 regression: None,
                 target: reg.target,
-                graph: adag,
+                commits,
                 valid_nodes: valid_nodes.clone(),
                 log_path,
                 counter,
+                scheduling,
                 _marker: PhantomData,
             }
         } else {
@@ -109,10 +111,7 @@ parents: {:?}
             );
 
             if let Some(lp) = &log_path {
-                log::write_to_file(&format!("Extended Parent Search ({}): Initial Candidates {:?}\n", counter, p), &main_log_file(lp));
-                let path_file = log::create_file(&format!("{}_parents", counter), lp);
-                let path_string = p.clone().make_contiguous().join("\n");
-                log::write_to_file(&path_string, &path_file);
+                log::write_event(lp, Event::ExtendedSearchStep { search: counter, step: "parents", commits: Vec::from(p.clone()) });
             }
 
             ExtendedSearch {
@@ -124,10 +123,11 @@ parents: {:?}
                 interrupts: vec![],
                 regression: None,
                 target: reg.target,
-                graph: adag,
+                commits,
                 valid_nodes: valid_nodes.clone(),
                 log_path,
                 counter,
+                scheduling,
                 _marker: PhantomData,
             }
         }
@@ -146,9 +146,8 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
     for ExtendedSearch<P, S, E>
 {
     fn add_result(&mut self, commit: String, result: super::TestResult) {
-        let index = self.graph.index(&commit);
-        let node = self.graph.graph.node_weight_mut(index).unwrap();
-        node.result = Some(result.clone());
+        let index = self.commits.graph.index(&commit);
+        self.commits.results.insert(index, result.clone());
 
         if result == TestResult::True {
             self.valid_nodes.insert(index);
@@ -166,9 +165,9 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
 
                 while !q.is_empty() {
                     let current = q.pop_front().unwrap();
-                    let current_index = self.graph.index(&current);
+                    let current_index = self.commits.graph.index(&current);
 
-                    let res = self.graph.node(&current).result;
+                    let res = self.commits.result(current_index).cloned();
                     match res {
                         Some(r) => match r {
                             TestResult::True => {}
@@ -178,9 +177,9 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
                                 break;
                             }
                             TestResult::Ignore => {
-                                let parents = self.graph.graph.parents(current_index);
-                                for (_, parent_index) in parents.iter(&self.graph.graph) {
-                                    let parent = self.graph.node_from_index(parent_index).hash;
+                                let graph = &self.commits.graph.graph;
+                                for (_, parent_index) in graph.parents(current_index).iter(graph) {
+                                    let parent = self.commits.graph.hash_from_index(parent_index);
                                     if vis.insert(parent.to_string()) {
                                         q.push_back(parent)
                                     }
@@ -189,11 +188,9 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
                         },
                         None => {
                             if let Some(log_path) = &self.log_path {
-                                let file_path = log_path.join(format!("{}_parents", self.counter));
-                                println!("aaa {:?}", file_path);
-                                log::write_to_file(&format!("{}\n", &current), &file_path);
+                                log::write_event(log_path, Event::ExtendedSearchStep { search: self.counter, step: "parent", commits: vec![current.clone()] });
                             }
-                            
+
                             ps.parents.push_back(current); 
                         }
                     }
@@ -217,14 +214,14 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
 
         //When we found a invalid parent, we start with the second phase.
         if let Some(nt) = new_target {
-            let search = create_sub::<P, S, E>(&self.graph, nt, &self.valid_nodes, &self.log_path, self.counter);
+            let search = create_sub::<P, S, E>(&self.commits, nt, &self.valid_nodes, &self.log_path, self.counter, self.scheduling);
             self.parents = None;
             self.sub = search;
             self.check_sub_done();
         }
     }
 
-    fn next_job(&mut self, capacity: u32, expected_capacity: u32) -> super::AlgorithmResponse {
+    fn next_job(&mut self, capacity: u32, expected_capacity: u32) -> super::AlgorithmResponse<'_> {
         if let Some(p) = &mut self.parents {
             match p.parents.pop_front() {
                 Some(hash) => {
@@ -261,23 +258,27 @@ impl<P: PathSelection, S: PathAlgorithm + RegressionAlgorithm, E: Clone> Regress
             Some(r) => vec![RegressionPoint {
                 regression_point: r.clone(),
                 target: self.target.clone(),
+                candidates: vec![],
+                probabilities: vec![],
             }],
             None => vec![],
         }
     }
 }
 
-fn create_sub<P: PathSelection, S: PathAlgorithm, E: Clone>(graph: &Adag<RPANode, E>, target: String, valid_nodes: &HashSet<NodeIndex>, log_path: &Option<std::path::PathBuf>, counter: usize) -> Option<S> {
+fn create_sub<P: PathSelection, S: PathAlgorithm, E: Clone>(commits: &Commits<E>, target: String, valid_nodes: &HashSet<NodeIndex>, log_path: &Option<std::path::PathBuf>, counter: usize, scheduling: Scheduling) -> Option<S> {
+    let graph = &commits.graph;
     let target_index = graph.index(&target);
     let targets = HashSet::from([target_index]);
 
-    let ordering = P::calculate_distances(graph, &targets, valid_nodes);
+    let mut distances = P::Distances::default();
+    let ordering = P::calculate_distances(commits, &targets, valid_nodes, &mut distances);
     let ((source_index, _), _) = ordering.peek().unwrap();
-    let path = P::extract_path(graph, *source_index, target_index);
+    let path = P::extract_path(commits, &distances, *source_index, target_index);
 
     let hash_path = path
         .iter()
-        .map(|i| graph.node_from_index(*i).hash)
+        .map(|i| graph.hash_from_index(*i))
         .collect::<VecDeque<String>>();
     
     let path_len = hash_path.len();
@@ -285,13 +286,10 @@ fn create_sub<P: PathSelection, S: PathAlgorithm, E: Clone>(graph: &Adag<RPANode
     let target_hash = graph.hash_from_index(target_index);
 
     if let Some(log_path) = log_path {
-        log::write_to_file(&format!("Extended Path Search ({}): From {} to {}. Path Length: {}\n", counter, source_hash, target_hash, path_len), &main_log_file(log_path));
-        let path_file = log::create_file(&format!("{}_path", counter), log_path);
-        let path_string = hash_path.clone().make_contiguous().join("\n");
-        log::write_to_file(&path_string, &path_file);
+        log::write_event(log_path, Event::ExtendedSearchStep { search: counter, step: "path", commits: Vec::from(hash_path.clone()) });
     }
 
-    let search = S::new(hash_path);
+    let search = S::new(hash_path, scheduling);
 
     eprintln!(
         "ExRPA - Algorithm:
@@ -1,13 +1,27 @@
-use super::TestResult;
+use std::collections::HashMap;
+
+use daggy::NodeIndex;
+
+use crate::graph::Adag;
+
+use super::{interval_search::Scheduling, TestResult};
 
 pub struct Settings {
     pub propagate: bool,
     pub extended_search: bool,
+    pub scheduling: Scheduling,
 }
 
-#[derive(Debug, Clone, PartialEq, Hash, Eq)]
-pub struct RPANode {
-    pub result: Option<TestResult>,
-    pub hash: String,
-    // pub distance: u32,
-}
\ No newline at end of file
+/// Commits of a search and their results so far. Clones share the graph, only
+/// the results are copied.
+#[derive(Debug, Clone)]
+pub struct Commits<E> {
+    pub graph: Adag<E>,
+    pub results: HashMap<NodeIndex, TestResult>,
+}
+
+impl<E> Commits<E> {
+    pub fn result(&self, index: NodeIndex) -> Option<&TestResult> {
+        self.results.get(&index)
+    }
+}
@@ -0,0 +1,493 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, FixedOffset};
+use serde_json::Value;
+
+use crate::{
+    dvcs::{git::Git, gitoxide::Gitoxide, DVCS},
+    log, output,
+};
+
+const TIMELINE_WIDTH: f64 = 1000.0;
+const ROW_HEIGHT: f64 = 24.0;
+
+struct Query {
+    pid: Option<u64>,
+    commit: String,
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+    result: String,
+    /// Name of the result in the terms of the search, missing in older logs.
+    term: Option<String>,
+    times: Option<(f64, f64, f64)>,
+    /// Known before the search, e.g. from a `git bisect log`.
+    imported: bool,
+}
+
+struct Regression {
+    target: String,
+    regression_point: String,
+    candidates: Vec<String>,
+}
+
+#[derive(Default)]
+struct Search {
+    arguments: Value,
+    repository: Option<PathBuf>,
+    start: Option<DateTime<FixedOffset>>,
+    queries: Vec<Query>,
+    regressions: Vec<Regression>,
+    interrupts: u32,
+    summary: Option<Value>,
+}
+
+/// Renders the event log of a search as a self-contained HTML page. `log` is
+/// either the directory of a single search or a `--log` directory, in which
+/// case the latest search is used.
+pub fn report(log: &Path, output: Option<&Path>) {
+    let directory = match find_search(log) {
+        Some(directory) => directory,
+        None => {
+            eprintln!("No event log found in {}", log.display());
+            return;
+        }
+    };
+
+    let search = match read_events(&log::event_path(&directory)) {
+        Ok(search) => search,
+        Err(err) => {
+            eprintln!(
+                "Couldn't read event log of {}: {}",
+                directory.display(),
+                err
+            );
+            return;
+        }
+    };
+
+    //Commits are looked up with the backend of the search.
+    let render = match search.arguments["backend"].as_str() {
+        Some("gitoxide") => render::<Gitoxide>,
+        _ => render::<Git>,
+    };
+    let html = render(&search, &directory);
+    let output = output
+        .map(|path| path.to_path_buf())
+        .unwrap_or_else(|| directory.join("report.html"));
+    match fs::write(&output, html) {
+        Ok(_) => println!("Report written to {}", output.display()),
+        Err(err) => eprintln!("Couldn't write {}: {}", output.display(), err),
+    }
+}
+
+fn find_search(log: &Path) -> Option<PathBuf> {
+    if log::event_path(log).is_file() {
+        return Some(log.to_path_buf());
+    }
+
+    //Directories of searches are named by their start time.
+    fs::read_dir(log)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| log::event_path(path).is_file())
+        .max()
+}
+
+fn read_events(path: &Path) -> Result<Search, String> {
+    let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let mut search = Search::default();
+    let mut running = HashMap::<String, (u64, DateTime<FixedOffset>)>::new();
+
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: Value =
+            serde_json::from_str(line).map_err(|err| format!("line {}: {}", number + 1, err))?;
+        let time = event["time"]
+            .as_str()
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .ok_or(format!("line {}: invalid time", number + 1))?;
+        let commit = event["commit"].as_str().unwrap_or_default().to_string();
+
+        match event["event"].as_str().unwrap_or_default() {
+            "search_started" => {
+                let repository = event["arguments"]["repository"].as_str().map(PathBuf::from);
+                let working_directory = event["working_directory"].as_str().map(PathBuf::from);
+                search.repository = match (working_directory, repository) {
+                    (Some(directory), Some(repository)) => Some(directory.join(repository)),
+                    (_, repository) => repository,
+                };
+                search.arguments = event["arguments"].clone();
+                search.start = Some(time);
+            }
+            "job_dispatched" => {
+                let pid = event["pid"].as_u64().unwrap_or_default();
+                running.insert(commit, (pid, time));
+            }
+            "job_finished" | "job_failed" => {
+                let (pid, start) = running.remove(&commit).unwrap_or((0, time));
+                let (result, times) = match event["result"].as_str() {
+                    Some(result) => (
+                        result.to_string(),
+                        Some((
+                            event["all"].as_f64().unwrap_or_default(),
+                            event["setup"].as_f64().unwrap_or_default(),
+                            event["query"].as_f64().unwrap_or_default(),
+                        )),
+                    ),
+                    None => (event["error"].as_str().unwrap_or("Error").to_string(), None),
+                };
+                search.queries.push(Query {
+                    pid: Some(pid),
+                    commit,
+                    start,
+                    end: time,
+                    result,
+                    term: event["term"].as_str().map(str::to_string),
+                    times,
+                    imported: false,
+                });
+            }
+            kind @ ("cached_result" | "imported_result") => search.queries.push(Query {
+                pid: None,
+                commit,
+                start: time,
+                end: time,
+                result: event["result"].as_str().unwrap_or_default().to_string(),
+                term: event["term"].as_str().map(str::to_string),
+                times: None,
+                imported: kind == "imported_result",
+            }),
+            "interrupt_issued" => search.interrupts += 1,
+            "regression_found" => search.regressions.push(Regression {
+                target: event["target"].as_str().unwrap_or_default().to_string(),
+                regression_point: event["regression_point"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                candidates: event["candidates"]
+                    .as_array()
+                    .map(|candidates| {
+                        candidates
+                            .iter()
+                            .filter_map(|c| c.as_str().map(|c| c.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            }),
+            "search_finished" => search.summary = Some(event),
+            _ => {}
+        }
+    }
+
+    search.queries.sort_by_key(|query| query.start);
+    Ok(search)
+}
+
+fn render<T: DVCS>(search: &Search, directory: &Path) -> String {
+    let mut html = String::new();
+    let title = format!(
+        "crs report {}",
+        directory.file_name().unwrap_or_default().to_string_lossy()
+    );
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; color: #222; }}
+table {{ border-collapse: collapse; margin-bottom: 2em; }}
+th, td {{ border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }}
+code, pre {{ font-family: monospace; }}
+.True {{ fill: #4caf50; color: #2e7d32; }}
+.False {{ fill: #e53935; color: #c62828; }}
+.Ignore {{ fill: #9e9e9e; color: #616161; }}
+.Error {{ fill: #ff9800; color: #ef6c00; }}
+</style>
+</head>
+<body>
+<h1>{}</h1>
+",
+        escape(&title),
+        escape(&title)
+    );
+
+    render_regressions::<T>(&mut html, search);
+    render_stats(&mut html, search);
+    render_timeline(&mut html, search);
+    render_queries(&mut html, search, directory);
+    render_arguments(&mut html, search);
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_regressions<T: DVCS>(html: &mut String, search: &Search) {
+    html.push_str("<h2>Regression Points</h2>\n");
+    if search.regressions.is_empty() {
+        html.push_str("<p>No regression point was found.</p>\n");
+        return;
+    }
+
+    let repository = search
+        .repository
+        .as_ref()
+        .map(|path| path.display().to_string());
+    let info = |commit: &str| {
+        repository
+            .as_ref()
+            .and_then(|repository| T::get_commit_info(repository, commit))
+            .unwrap_or_else(|| commit.to_string())
+    };
+
+    html.push_str("<table>\n<tr><th>Target</th><th>Regression Point</th></tr>\n");
+    for regression in &search.regressions {
+        let point = if regression.candidates.is_empty() {
+            format!(
+                "<pre>{}</pre>",
+                escape(info(&regression.regression_point).trim())
+            )
+        } else {
+            let candidates = regression
+                .candidates
+                .iter()
+                .map(|candidate| escape(info(candidate).trim()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("ambiguous, candidates:<pre>{}</pre>", candidates)
+        };
+        let _ = writeln!(
+            html,
+            "<tr><td><code>{}</code></td><td>{}</td></tr>",
+            escape(&regression.target),
+            point
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn render_stats(html: &mut String, search: &Search) {
+    let mut results = BTreeMap::<&str, u32>::new();
+    for query in &search.queries {
+        *results.entry(result_class(&query.result)).or_default() += 1;
+    }
+    let cached = search
+        .queries
+        .iter()
+        .filter(|q| q.pid.is_none() && !q.imported)
+        .count();
+    let imported = search.queries.iter().filter(|q| q.imported).count();
+
+    html.push_str("<h2>Statistics</h2>\n<table>\n");
+    let mut row = |name: &str, value: String| {
+        let _ = writeln!(
+            html,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            name,
+            escape(&value)
+        );
+    };
+
+    if let Some(summary) = &search.summary {
+        row("Runtime (seconds)", format_seconds(&summary["duration"]));
+        row("Cancelled", summary["cancelled"].to_string());
+        row("Commits tested", summary["queries"].to_string());
+        for time in ["all", "setup", "query"] {
+            row(
+                &format!("Total {} (seconds)", time),
+                format_seconds(&summary["total"][time]),
+            );
+            row(
+                &format!("Average {} (seconds)", time),
+                format_seconds(&summary["average"][time]),
+            );
+        }
+    } else {
+        row("Runtime (seconds)", "unfinished".to_string());
+    }
+    row("Results from cache", cached.to_string());
+    if imported > 0 {
+        row("Imported results", imported.to_string());
+    }
+    row("Interrupts", search.interrupts.to_string());
+    for (result, amount) in results {
+        row(&format!("Results {}", result), amount.to_string());
+    }
+    html.push_str("</table>\n");
+}
+
+fn render_timeline(html: &mut String, search: &Search) {
+    html.push_str("<h2>Timeline</h2>\n");
+    let start = match search.start.or(search.queries.first().map(|q| q.start)) {
+        Some(start) => start,
+        None => return,
+    };
+    let end = search
+        .queries
+        .iter()
+        .map(|query| query.end)
+        .max()
+        .unwrap_or(start);
+    let span = seconds(start, end).max(0.001);
+
+    let pids = search
+        .queries
+        .iter()
+        .filter_map(|query| query.pid)
+        .collect::<std::collections::BTreeSet<_>>();
+    let row = |pid: u64| pids.iter().position(|p| *p == pid).unwrap_or_default() as f64;
+    let label_width = 100.0;
+
+    let _ = writeln!(
+        html,
+        "<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\" font-size=\"12\">",
+        label_width + TIMELINE_WIDTH,
+        (pids.len() as f64 + 1.0) * ROW_HEIGHT
+    );
+    for pid in &pids {
+        let _ = writeln!(
+            html,
+            "<text x=\"0\" y=\"{}\">Process {}</text>",
+            row(*pid) * ROW_HEIGHT + 16.0,
+            pid
+        );
+    }
+    for query in search.queries.iter().filter(|q| q.pid.is_some()) {
+        let x = seconds(start, query.start) / span * TIMELINE_WIDTH;
+        let width = (seconds(query.start, query.end) / span * TIMELINE_WIDTH).max(1.0);
+        let _ = writeln!(
+            html,
+            "<rect class=\"{}\" x=\"{:.1}\" y=\"{}\" width=\"{:.1}\" height=\"{}\"><title>{} {} ({:.3}s)</title></rect>",
+            result_class(&query.result),
+            label_width + x,
+            row(query.pid.unwrap()) * ROW_HEIGHT + 2.0,
+            width,
+            ROW_HEIGHT - 4.0,
+            escape(&query.commit),
+            escape(&query.result),
+            seconds(query.start, query.end)
+        );
+    }
+    let _ = writeln!(
+        html,
+        "<text x=\"{}\" y=\"{}\">0s</text><text x=\"{}\" y=\"{}\" text-anchor=\"end\">{:.3}s</text>",
+        label_width,
+        pids.len() as f64 * ROW_HEIGHT + 16.0,
+        label_width + TIMELINE_WIDTH,
+        pids.len() as f64 * ROW_HEIGHT + 16.0,
+        span
+    );
+    html.push_str("</svg>\n");
+}
+
+//The end of the outputs is part of the page, so it can be moved elsewhere.
+fn render_queries(html: &mut String, search: &Search, directory: &Path) {
+    //Older logs don't have the argument.
+    let lines = search.arguments["output_tail"].as_u64().unwrap_or(20) as usize;
+    let _ = write!(
+        html,
+        "<h2>Queries</h2>
+<table>
+<tr><th>Commit</th><th>Process</th><th>Result</th><th>All (s)</th><th>Setup (s)</th><th>Query (s)</th><th>Output (last {} lines)</th></tr>
+",
+        lines
+    );
+    let output = log::output_path(directory);
+    for query in &search.queries {
+        let process = match query.pid {
+            Some(pid) => pid.to_string(),
+            None if query.imported => "imported".to_string(),
+            None => "cache".to_string(),
+        };
+        let times = match query.times {
+            Some((all, setup, query)) => {
+                format!(
+                    "<td>{:.3}</td><td>{:.3}</td><td>{:.3}</td>",
+                    all, setup, query
+                )
+            }
+            None => "<td></td><td></td><td></td>".to_string(),
+        };
+        let outputs = ["stdout", "stderr", "build_stdout", "build_stderr"]
+            .iter()
+            .filter(|_| lines > 0)
+            .filter_map(|kind| {
+                let path = output.join(format!("{}_{}", query.commit, kind));
+                let text = output::tail(&path, lines).filter(|text| !text.is_empty())?;
+                Some(format!(
+                    "<details><summary>{}</summary><pre>{}</pre></details>",
+                    kind,
+                    escape(&text)
+                ))
+            })
+            .collect::<String>();
+        let _ = writeln!(
+            html,
+            "<tr><td><code>{}</code></td><td>{}</td><td class=\"{}\">{}</td>{}<td>{}</td></tr>",
+            escape(&query.commit),
+            process,
+            result_class(&query.result),
+            escape(query.term.as_ref().unwrap_or(&query.result)),
+            times,
+            outputs
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn render_arguments(html: &mut String, search: &Search) {
+    html.push_str("<h2>Arguments</h2>\n<table>\n");
+    if let Some(arguments) = search.arguments.as_object() {
+        for (name, value) in arguments {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            let _ = writeln!(
+                html,
+                "<tr><th>{}</th><td><code>{}</code></td></tr>",
+                escape(name),
+                escape(&value)
+            );
+        }
+    }
+    html.push_str("</table>\n");
+}
+
+fn result_class(result: &str) -> &'static str {
+    match result {
+        "True" => "True",
+        "False" => "False",
+        "Ignore" => "Ignore",
+        _ => "Error",
+    }
+}
+
+fn seconds(from: DateTime<FixedOffset>, to: DateTime<FixedOffset>) -> f64 {
+    (to - from).num_milliseconds() as f64 / 1000.0
+}
+
+fn format_seconds(value: &Value) -> String {
+    match value.as_f64() {
+        Some(seconds) => format!("{:.3}", seconds),
+        None => "-".to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
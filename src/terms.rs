@@ -0,0 +1,138 @@
+use crate::regression::TestResult;
+
+/// Names of the two states of a search. The algorithms always look for the
+/// first commit in the new state, the old state is the one of the sources.
+/// When looking for a fix, a passing test means the new state, so the results
+/// of the test are inverted.
+#[derive(Debug, Clone)]
+pub struct Terms {
+    pub old: String,
+    pub new: String,
+    pub find_fix: bool,
+}
+
+impl Terms {
+    pub fn new(find_fix: bool, old: Option<String>, new: Option<String>) -> Self {
+        let (default_old, default_new) = if find_fix {
+            ("broken", "fixed")
+        } else {
+            ("good", "bad")
+        };
+        Terms {
+            old: old.unwrap_or_else(|| default_old.to_string()),
+            new: new.unwrap_or_else(|| default_new.to_string()),
+            find_fix,
+        }
+    }
+
+    /// Maps the result of the test to the result for the algorithms.
+    pub fn map(&self, result: TestResult) -> TestResult {
+        match (self.find_fix, result) {
+            (true, TestResult::True) => TestResult::False,
+            (true, TestResult::False) => TestResult::True,
+            (_, result) => result,
+        }
+    }
+
+    /// Name of a result of the algorithms.
+    pub fn name(&self, result: &TestResult) -> &str {
+        match result {
+            TestResult::True => &self.old,
+            TestResult::False => &self.new,
+            TestResult::Ignore => "skip",
+        }
+    }
+
+    /// Parses a result given by a user or a `git bisect log`. Like in
+    /// `git bisect` with custom terms, only the terms of the search and the
+    /// aliases `old` and `new` are accepted, so `good` and `bad` only with the
+    /// default terms.
+    pub fn parse(&self, word: &str) -> Result<TestResult, String> {
+        if word == self.old {
+            Ok(TestResult::True)
+        } else if word == self.new {
+            Ok(TestResult::False)
+        } else if word == "skip" {
+            Ok(TestResult::Ignore)
+        } else if word == "old" {
+            Ok(TestResult::True)
+        } else if word == "new" {
+            Ok(TestResult::False)
+        } else {
+            Err(format!(
+                "unknown result \"{}\", expected {}, {} or skip",
+                word, self.old, self.new
+            ))
+        }
+    }
+
+    /// Heading of the found commit in the summary.
+    pub fn point_label(&self) -> String {
+        if self.find_fix || self.old != "good" || self.new != "bad" {
+            format!("First {} commit", self.new)
+        } else {
+            "Regression Point".to_string()
+        }
+    }
+}
+
+impl Default for Terms {
+    fn default() -> Self {
+        Terms::new(false, None, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(old: &str, new: &str) -> Terms {
+        Terms::new(false, Some(old.to_string()), Some(new.to_string()))
+    }
+
+    #[test]
+    fn parses_default_terms_and_aliases() {
+        let terms = Terms::default();
+        assert_eq!(terms.parse("good"), Ok(TestResult::True));
+        assert_eq!(terms.parse("old"), Ok(TestResult::True));
+        assert_eq!(terms.parse("bad"), Ok(TestResult::False));
+        assert_eq!(terms.parse("new"), Ok(TestResult::False));
+        assert_eq!(terms.parse("skip"), Ok(TestResult::Ignore));
+    }
+
+    #[test]
+    fn custom_terms_replace_good_and_bad() {
+        let terms = Terms::new(true, None, None);
+        assert_eq!(terms.parse("broken"), Ok(TestResult::True));
+        assert_eq!(terms.parse("fixed"), Ok(TestResult::False));
+        assert_eq!(terms.parse("old"), Ok(TestResult::True));
+        assert_eq!(
+            terms.parse("good"),
+            Err("unknown result \"good\", expected broken, fixed or skip".to_string())
+        );
+        assert!(terms.parse("bad").is_err());
+    }
+
+    #[test]
+    fn custom_terms_win_over_aliases() {
+        //The aliases mean the opposite here.
+        let terms = custom("new", "old");
+        assert_eq!(terms.parse("new"), Ok(TestResult::True));
+        assert_eq!(terms.parse("old"), Ok(TestResult::False));
+
+        let terms = custom("bad", "good");
+        assert_eq!(terms.parse("bad"), Ok(TestResult::True));
+        assert_eq!(terms.parse("good"), Ok(TestResult::False));
+    }
+
+    #[test]
+    fn fixes_invert_the_results() {
+        let terms = Terms::new(true, None, None);
+        assert_eq!(terms.map(TestResult::True), TestResult::False);
+        assert_eq!(terms.map(TestResult::False), TestResult::True);
+        assert_eq!(terms.map(TestResult::Ignore), TestResult::Ignore);
+        assert_eq!(terms.name(&TestResult::False), "fixed");
+        assert_eq!(terms.point_label(), "First fixed commit");
+        assert_eq!(Terms::default().point_label(), "Regression Point");
+    }
+}
@@ -0,0 +1,242 @@
+use std::{fs, path::Path, process::ExitStatus, str::FromStr};
+
+use regex::Regex;
+
+use crate::regression::TestResult;
+
+/// What a finished test means for the search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Result(TestResult),
+    /// Stops the search.
+    Abort,
+}
+
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Code(i32, i32),
+    /// The test was killed by a signal, `None` matches any signal.
+    Signal(Option<i32>),
+    Stdout(Regex),
+    Stderr(Regex),
+}
+
+/// Maps a finished test to a verdict, e.g. `code:1-127=bad` or
+/// `stdout:FAIL: test_foo=bad`.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub condition: Condition,
+    pub verdict: Verdict,
+}
+
+/// Rules are checked in order, the first rule that matches decides. If no rule
+/// matches, the default rules are used: 0 is good, 125 is skip, 1-127 is bad,
+/// everything else stops the search.
+#[derive(Debug, Clone, Default)]
+pub struct Verdicts {
+    rules: Vec<Rule>,
+}
+
+impl Verdicts {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Verdicts { rules }
+    }
+
+    pub fn evaluate(&self, status: &ExitStatus, stdout: &Path, stderr: &Path) -> Verdict {
+        let code = status.code();
+        let signal = signal(status);
+        let mut outputs = (None, None);
+
+        for rule in &self.rules {
+            let matches = match &rule.condition {
+                Condition::Code(from, to) => code.map(|c| *from <= c && c <= *to).unwrap_or(false),
+                Condition::Signal(expected) => match (signal, expected) {
+                    (Some(_), None) => true,
+                    (Some(s), Some(e)) => s == *e,
+                    (None, _) => false,
+                },
+                Condition::Stdout(regex) => {
+                    regex.is_match(outputs.0.get_or_insert_with(|| read(stdout)))
+                }
+                Condition::Stderr(regex) => {
+                    regex.is_match(outputs.1.get_or_insert_with(|| read(stderr)))
+                }
+            };
+            if matches {
+                return rule.verdict.clone();
+            }
+        }
+
+        match code {
+            Some(0) => Verdict::Result(TestResult::True),
+            Some(125) => Verdict::Result(TestResult::Ignore),
+            Some(c) if (1..128).contains(&c) => Verdict::Result(TestResult::False),
+            _ => Verdict::Abort,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: &ExitStatus) -> Option<i32> {
+    std::os::unix::process::ExitStatusExt::signal(status)
+}
+
+#[cfg(not(unix))]
+fn signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+fn read(path: &Path) -> String {
+    fs::read(path)
+        .map(|content| String::from_utf8_lossy(&content).to_string())
+        .unwrap_or_default()
+}
+
+impl FromStr for Verdict {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "good" => Ok(Verdict::Result(TestResult::True)),
+            "bad" => Ok(Verdict::Result(TestResult::False)),
+            "skip" => Ok(Verdict::Result(TestResult::Ignore)),
+            "abort" => Ok(Verdict::Abort),
+            _ => Err(format!(
+                "unknown verdict \"{}\", expected good, bad, skip or abort",
+                s
+            )),
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        //Verdicts never contain '=', but the patterns might.
+        let (condition, verdict) = s
+            .rsplit_once('=')
+            .ok_or(format!("\"{}\" has to look like <CONDITION>=<VERDICT>", s))?;
+        let (kind, argument) = match condition.split_once(':') {
+            Some((kind, argument)) => (kind, Some(argument)),
+            None => (condition, None),
+        };
+        let number = |n: &str| {
+            n.trim()
+                .parse::<i32>()
+                .map_err(|_| format!("\"{}\" is not a number", n))
+        };
+        let regex = |pattern: Option<&str>| {
+            Regex::new(pattern.unwrap_or_default()).map_err(|err| err.to_string())
+        };
+
+        let condition = match (kind, argument) {
+            ("code", Some(range)) => match range.split_once('-') {
+                Some((from, to)) => Condition::Code(number(from)?, number(to)?),
+                None => Condition::Code(number(range)?, number(range)?),
+            },
+            ("signal", None) => Condition::Signal(None),
+            ("signal", Some(signal)) => Condition::Signal(Some(number(signal)?)),
+            ("stdout", pattern) => Condition::Stdout(regex(pattern)?),
+            ("stderr", pattern) => Condition::Stderr(regex(pattern)?),
+            _ => {
+                return Err(format!(
+                    "unknown condition \"{}\", expected code:<FROM>[-<TO>], signal[:<SIGNAL>], stdout:<REGEX> or stderr:<REGEX>",
+                    condition
+                ))
+            }
+        };
+
+        Ok(Rule {
+            condition,
+            verdict: verdict.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+
+    use super::*;
+
+    const GOOD: Verdict = Verdict::Result(TestResult::True);
+    const BAD: Verdict = Verdict::Result(TestResult::False);
+    const SKIP: Verdict = Verdict::Result(TestResult::Ignore);
+
+    fn evaluate(rules: &[&str], status: i32, stdout: &str) -> Verdict {
+        let rules = rules.iter().map(|rule| rule.parse().unwrap()).collect();
+        //Tests run in parallel.
+        let path = std::env::temp_dir().join(format!(
+            "crs_verdict_{}_{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        fs::write(&path, stdout).unwrap();
+        let verdict =
+            Verdicts::new(rules).evaluate(&ExitStatus::from_raw(status), &path, Path::new(""));
+        fs::remove_file(&path).unwrap();
+        verdict
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rule = "code:1-127=bad".parse::<Rule>().unwrap();
+        assert!(matches!(rule.condition, Condition::Code(1, 127)));
+        assert_eq!(rule.verdict, BAD);
+
+        let rule = "code: 3 =skip".parse::<Rule>().unwrap();
+        assert!(matches!(rule.condition, Condition::Code(3, 3)));
+        assert_eq!(rule.verdict, SKIP);
+
+        let rule = "signal=abort".parse::<Rule>().unwrap();
+        assert!(matches!(rule.condition, Condition::Signal(None)));
+        assert_eq!(rule.verdict, Verdict::Abort);
+
+        let rule = "signal:9=good".parse::<Rule>().unwrap();
+        assert!(matches!(rule.condition, Condition::Signal(Some(9))));
+
+        //Only the last '=' separates the verdict.
+        let rule = "stdout:a=b=bad".parse::<Rule>().unwrap();
+        assert!(matches!(&rule.condition, Condition::Stdout(regex) if regex.as_str() == "a=b"));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for (rule, error) in [
+            ("code:1", "has to look like <CONDITION>=<VERDICT>"),
+            ("code:x=bad", "\"x\" is not a number"),
+            ("code:1-=bad", "\"\" is not a number"),
+            ("code=bad", "unknown condition \"code\""),
+            ("exit:1=bad", "unknown condition \"exit:1\""),
+            ("stderr:(=bad", "regex parse error"),
+            ("code:1=fine", "unknown verdict \"fine\""),
+        ] {
+            let err = rule.parse::<Rule>().unwrap_err();
+            assert!(err.contains(error), "{}: {}", rule, err);
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let rules = ["stdout:flaky=skip", "code:1=good", "code:1-10=abort"];
+        let exit = |code: i32| code << 8;
+        assert_eq!(evaluate(&rules, exit(1), "flaky network"), SKIP);
+        assert_eq!(evaluate(&rules, exit(1), ""), GOOD);
+        assert_eq!(evaluate(&rules, exit(2), ""), Verdict::Abort);
+        assert_eq!(evaluate(&rules, exit(11), ""), BAD);
+    }
+
+    #[test]
+    fn defaults_without_matching_rule() {
+        let exit = |code: i32| code << 8;
+        assert_eq!(evaluate(&[], exit(0), ""), GOOD);
+        assert_eq!(evaluate(&[], exit(125), ""), SKIP);
+        assert_eq!(evaluate(&[], exit(127), ""), BAD);
+        assert_eq!(evaluate(&[], exit(128), ""), Verdict::Abort);
+        //Killed by SIGKILL.
+        assert_eq!(evaluate(&[], 9, ""), Verdict::Abort);
+        assert_eq!(evaluate(&["signal:9=bad"], 9, ""), BAD);
+        assert_eq!(evaluate(&["signal:15=bad"], 9, ""), Verdict::Abort);
+    }
+}